@@ -5,6 +5,7 @@ pub struct Config {
     pub server: Server,
     pub ssl: Option<Ssl>,
     pub database: Database,
+    pub cookie: Option<Cookie>,
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -29,6 +30,12 @@ pub struct Database {
     pub database: String,
 }
 
+#[derive(Deserialize, Debug, Clone)]
+pub struct Cookie {
+    /// Secret of at least 32 bytes that signs and encrypts cookies.
+    pub secret: String,
+}
+
 pub fn load() -> Result<Config, toml::de::Error> {
     let config_content = include_str!("../../maden.toml");
     toml::from_str(config_content)
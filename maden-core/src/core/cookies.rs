@@ -0,0 +1,151 @@
+use std::sync::Arc;
+
+use cookie::{Cookie, Key};
+
+use crate::core::error::MadenError;
+use crate::core::extractor::FromRequestParts;
+use crate::core::guard::header;
+use crate::core::http::{IntoResponse, Request, Response};
+
+/// Parses the `Cookie` header into a jar of original cookies.
+fn request_cookies(req: &Request) -> cookie::CookieJar {
+    let mut jar = cookie::CookieJar::new();
+    if let Some(header) = header(req, "Cookie") {
+        for cookie in Cookie::split_parse_encoded(header.to_string()).flatten() {
+            jar.add_original(cookie);
+        }
+    }
+    jar
+}
+
+/// The key from `[cookie] secret` in the config, or managed with `Maden::manage`.
+fn cookie_key(req: &Request) -> Result<Arc<Key>, MadenError> {
+    req.state.get::<Key>()
+        .ok_or_else(|| MadenError::internal_server_error("No cookie key is configured; set `[cookie] secret` in maden.toml"))
+}
+
+/// Extract the cookies sent with the request
+///
+/// Cookies added or removed here are set on the response when the jar is
+/// returned alongside it:
+///
+/// ```ignore
+/// #[post("/theme")]
+/// async fn set_theme(mut jar: CookieJar) -> (CookieJar, &'static str) {
+///     jar.add(Cookie::build(("theme", "dark")).path("/"));
+///     (jar, "ok")
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct CookieJar {
+    jar: cookie::CookieJar,
+}
+
+impl CookieJar {
+    pub fn get(&self, name: &str) -> Option<&Cookie<'static>> {
+        self.jar.get(name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Cookie<'static>> {
+        self.jar.iter()
+    }
+
+    pub fn add(&mut self, cookie: impl Into<Cookie<'static>>) {
+        self.jar.add(cookie);
+    }
+
+    /// Deletes a cookie. Its path and domain must match the ones it was set with.
+    pub fn remove(&mut self, cookie: impl Into<Cookie<'static>>) {
+        self.jar.remove(cookie);
+    }
+}
+
+impl FromRequestParts for CookieJar {
+    async fn from_request_parts(req: &Request) -> Result<Self, MadenError> {
+        Ok(CookieJar { jar: request_cookies(req) })
+    }
+}
+
+/// Extract cookies signed with the configured key
+///
+/// Values are readable by the client, but cookies it has altered are ignored.
+#[derive(Clone)]
+pub struct SignedCookieJar {
+    jar: cookie::CookieJar,
+    key: Arc<Key>,
+}
+
+impl SignedCookieJar {
+    /// The cookie called `name`, if its signature is valid.
+    pub fn get(&self, name: &str) -> Option<Cookie<'static>> {
+        self.jar.signed(&self.key).get(name)
+    }
+
+    pub fn add(&mut self, cookie: impl Into<Cookie<'static>>) {
+        self.jar.signed_mut(&self.key).add(cookie);
+    }
+
+    pub fn remove(&mut self, cookie: impl Into<Cookie<'static>>) {
+        self.jar.signed_mut(&self.key).remove(cookie);
+    }
+}
+
+impl FromRequestParts for SignedCookieJar {
+    async fn from_request_parts(req: &Request) -> Result<Self, MadenError> {
+        Ok(SignedCookieJar { jar: request_cookies(req), key: cookie_key(req)? })
+    }
+}
+
+/// Extract cookies encrypted with the configured key
+///
+/// Values can be neither read nor altered by the client.
+#[derive(Clone)]
+pub struct PrivateCookieJar {
+    jar: cookie::CookieJar,
+    key: Arc<Key>,
+}
+
+impl PrivateCookieJar {
+    /// The decrypted cookie called `name`, if it was encrypted with this key.
+    pub fn get(&self, name: &str) -> Option<Cookie<'static>> {
+        self.jar.private(&self.key).get(name)
+    }
+
+    pub fn add(&mut self, cookie: impl Into<Cookie<'static>>) {
+        self.jar.private_mut(&self.key).add(cookie);
+    }
+
+    pub fn remove(&mut self, cookie: impl Into<Cookie<'static>>) {
+        self.jar.private_mut(&self.key).remove(cookie);
+    }
+}
+
+impl FromRequestParts for PrivateCookieJar {
+    async fn from_request_parts(req: &Request) -> Result<Self, MadenError> {
+        Ok(PrivateCookieJar { jar: request_cookies(req), key: cookie_key(req)? })
+    }
+}
+
+macro_rules! impl_into_response_with_jar {
+    ($($jar:ty),*) => {
+        $(
+            impl IntoResponse for $jar {
+                fn into_response(self) -> Response {
+                    Response::new(200).with_cookies(&self.jar)
+                }
+            }
+
+            impl<T> IntoResponse for ($jar, T)
+            where
+                T: IntoResponse,
+            {
+                fn into_response(self) -> Response {
+                    let (jar, body) = self;
+                    body.into_response().with_cookies(&jar.jar)
+                }
+            }
+        )*
+    };
+}
+
+impl_into_response_with_jar!(CookieJar, SignedCookieJar, PrivateCookieJar);
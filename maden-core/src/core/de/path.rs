@@ -0,0 +1,212 @@
+use serde::de::{DeserializeSeed, Deserializer, IntoDeserializer, MapAccess, SeqAccess, Visitor};
+
+use super::{DeError, ValueDeserializer};
+
+/// Deserializes route parameters into `T`.
+///
+/// Structs and maps read the parameters by name, tuples and sequences by
+/// position, and scalars (numbers, bools, strings, unit enum variants)
+/// from the single parameter of the route.
+pub struct PathDeserializer<'de> {
+    kind: &'static str,
+    params: &'de [(String, String)],
+}
+
+impl<'de> PathDeserializer<'de> {
+    pub fn new(params: &'de [(String, String)]) -> Self {
+        Self { kind: "path parameter", params }
+    }
+
+    /// Same rules for the parameters captured from the route's host.
+    pub fn host(params: &'de [(String, String)]) -> Self {
+        Self { kind: "host parameter", params }
+    }
+
+    /// The single parameter, for types that deserialize from one value.
+    fn single(&self) -> Result<ValueDeserializer<'de>, DeError> {
+        match self.params {
+            [(name, value)] => Ok(ValueDeserializer { kind: self.kind, name, value }),
+            params => Err(DeError(format!("expected 1 {}, the route has {}", self.kind, params.len()))),
+        }
+    }
+}
+
+macro_rules! forward_to_single {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+                self.single()?.$method(visitor)
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for PathDeserializer<'de> {
+    type Error = DeError;
+
+    forward_to_single! {
+        deserialize_bool
+        deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64 deserialize_i128
+        deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64 deserialize_u128
+        deserialize_f32 deserialize_f64
+        deserialize_char deserialize_str deserialize_string
+        deserialize_bytes deserialize_byte_buf
+        deserialize_option deserialize_unit deserialize_identifier
+    }
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(self, name: &'static str, visitor: V) -> Result<V::Value, DeError> {
+        self.single()?.deserialize_unit_struct(name, visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, DeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        visitor.visit_seq(ParamSeq { kind: self.kind, params: self.params.iter() })
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value, DeError> {
+        if self.params.len() != len {
+            return Err(DeError(format!("expected {len} {}s, the route has {}", self.kind, self.params.len())));
+        }
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(self, _name: &'static str, len: usize, visitor: V) -> Result<V::Value, DeError> {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        visitor.visit_map(ParamMap { kind: self.kind, params: self.params.iter(), value: None })
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        self.single()?.deserialize_enum(name, variants, visitor)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        visitor.visit_unit()
+    }
+}
+
+struct ParamSeq<'de> {
+    kind: &'static str,
+    params: std::slice::Iter<'de, (String, String)>,
+}
+
+impl<'de> SeqAccess<'de> for ParamSeq<'de> {
+    type Error = DeError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>, DeError> {
+        match self.params.next() {
+            Some((name, value)) => seed.deserialize(ValueDeserializer { kind: self.kind, name, value }).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.params.len())
+    }
+}
+
+struct ParamMap<'de> {
+    kind: &'static str,
+    params: std::slice::Iter<'de, (String, String)>,
+    value: Option<(&'de str, &'de str)>,
+}
+
+impl<'de> MapAccess<'de> for ParamMap<'de> {
+    type Error = DeError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, DeError> {
+        match self.params.next() {
+            Some((name, value)) => {
+                self.value = Some((name, value));
+                seed.deserialize(name.as_str().into_deserializer()).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, DeError> {
+        let (name, value) = self.value.take().ok_or_else(|| DeError("value requested before key".to_string()))?;
+        seed.deserialize(ValueDeserializer { kind: self.kind, name, value })
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.params.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+
+    fn params(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect()
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Post {
+        user: u32,
+        slug: String,
+    }
+
+    #[test]
+    fn structs_read_params_by_name() {
+        let params = params(&[("slug", "hello"), ("user", "7")]);
+        let post = Post::deserialize(PathDeserializer::new(&params)).unwrap();
+        assert_eq!(post, Post { user: 7, slug: "hello".to_string() });
+    }
+
+    #[test]
+    fn tuples_read_params_by_position() {
+        let params = params(&[("user", "7"), ("slug", "hello")]);
+        let (user, slug) = <(u32, String)>::deserialize(PathDeserializer::new(&params)).unwrap();
+        assert_eq!((user, slug.as_str()), (7, "hello"));
+
+        let error = <(u32, String, u8)>::deserialize(PathDeserializer::new(&params)).unwrap_err();
+        assert_eq!(error.to_string(), "expected 3 path parameters, the route has 2");
+    }
+
+    #[test]
+    fn scalars_need_a_single_param() {
+        let one = params(&[("id", "42")]);
+        assert_eq!(u32::deserialize(PathDeserializer::new(&one)).unwrap(), 42);
+
+        let two = params(&[("a", "1"), ("b", "2")]);
+        let error = u32::deserialize(PathDeserializer::new(&two)).unwrap_err();
+        assert_eq!(error.to_string(), "expected 1 path parameter, the route has 2");
+    }
+
+    #[test]
+    fn reports_values_of_the_wrong_type() {
+        let params = params(&[("id", "abc")]);
+        let error = u32::deserialize(PathDeserializer::new(&params)).unwrap_err();
+        assert!(error.to_string().starts_with("path parameter `id` = `abc` is not a valid u32"), "{error}");
+
+        let error = u32::deserialize(PathDeserializer::host(&params)).unwrap_err();
+        assert!(error.to_string().starts_with("host parameter `id`"), "{error}");
+    }
+}
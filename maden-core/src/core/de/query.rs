@@ -0,0 +1,394 @@
+use serde::de::{self, DeserializeSeed, Deserializer, IntoDeserializer, MapAccess, SeqAccess, Visitor};
+use serde::forward_to_deserialize_any;
+
+use super::{DeError, ValueDeserializer};
+
+/// Deserializes decoded query string or form body pairs into `T`.
+///
+/// Deserialization is driven by the target type: `?zip=01234` stays a string
+/// for a `String` field and is parsed only for numeric ones. Repeated keys
+/// (`?id=1&id=2`, or `?id[]=1&id[]=2`) fill a `Vec<T>`, bracketed keys
+/// (`?user[name]=a&user[age]=3`) fill nested structs and maps, and a key
+/// without a value (`?flag`) reads as `true` for a `bool`.
+pub struct QueryDeserializer<'de> {
+    kind: &'static str,
+    pairs: &'de [(String, String)],
+}
+
+impl<'de> QueryDeserializer<'de> {
+    pub fn new(pairs: &'de [(String, String)]) -> Self {
+        Self { kind: "query parameter", pairs }
+    }
+
+    /// Same rules for an `application/x-www-form-urlencoded` body.
+    pub fn form(pairs: &'de [(String, String)]) -> Self {
+        Self { kind: "form field", pairs }
+    }
+
+    fn root(&self) -> Result<NodeDeserializer<'de>, DeError> {
+        let mut entries = Vec::new();
+        for (key, value) in self.pairs {
+            let mut segments = key_segments(key);
+            if segments.len() > 1 && segments.last() == Some(&"") {
+                // `key[]` appends to `key`
+                segments.pop();
+            }
+            insert(&mut entries, key, &segments, value)?;
+        }
+        Ok(NodeDeserializer { kind: self.kind, name: "", node: Node::Map(entries) })
+    }
+}
+
+/// Values grouped by key, with bracketed keys nested under their parent.
+enum Node<'de> {
+    Values(Vec<&'de str>),
+    Map(Vec<(&'de str, Node<'de>)>),
+}
+
+/// Splits `user[address][city]` into `["user", "address", "city"]`.
+/// Keys that are not well-formed bracket paths are kept whole.
+fn key_segments(key: &str) -> Vec<&str> {
+    let Some(open) = key.find('[').filter(|&open| open > 0) else {
+        return vec![key];
+    };
+
+    let mut segments = vec![&key[..open]];
+    let mut rest = &key[open..];
+    while let Some(inner) = rest.strip_prefix('[') {
+        let Some(close) = inner.find(']') else {
+            return vec![key];
+        };
+        segments.push(&inner[..close]);
+        rest = &inner[close + 1..];
+    }
+    if !rest.is_empty() {
+        return vec![key];
+    }
+    segments
+}
+
+fn insert<'de>(
+    entries: &mut Vec<(&'de str, Node<'de>)>,
+    key: &str,
+    segments: &[&'de str],
+    value: &'de str,
+) -> Result<(), DeError> {
+    let Some((first, rest)) = segments.split_first() else {
+        return Ok(());
+    };
+
+    let index = match entries.iter().position(|(name, _)| name == first) {
+        Some(index) => index,
+        None => {
+            let node = if rest.is_empty() { Node::Values(Vec::new()) } else { Node::Map(Vec::new()) };
+            entries.push((first, node));
+            entries.len() - 1
+        }
+    };
+
+    match (&mut entries[index].1, rest.is_empty()) {
+        (Node::Values(values), true) => values.push(value),
+        (Node::Map(children), false) => insert(children, key, rest, value)?,
+        _ => return Err(DeError(format!("key `{key}` is given both a value and nested keys"))),
+    }
+    Ok(())
+}
+
+macro_rules! forward_to_root {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+                self.root()?.$method(visitor)
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for QueryDeserializer<'de> {
+    type Error = DeError;
+
+    forward_to_root! {
+        deserialize_any deserialize_map deserialize_seq deserialize_option deserialize_ignored_any
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, name: &'static str, visitor: V) -> Result<V::Value, DeError> {
+        self.root()?.deserialize_newtype_struct(name, visitor)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        self.root()?.deserialize_struct(name, fields, visitor)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct tuple tuple_struct enum identifier
+    }
+}
+
+/// A key and everything given for it.
+struct NodeDeserializer<'de> {
+    kind: &'static str,
+    name: &'de str,
+    node: Node<'de>,
+}
+
+impl<'de> NodeDeserializer<'de> {
+    /// The single value of the key, for types that deserialize from one value.
+    fn value(self) -> Result<ValueDeserializer<'de>, DeError> {
+        match self.node {
+            Node::Values(values) if values.len() == 1 => Ok(ValueDeserializer { kind: self.kind, name: self.name, value: values[0] }),
+            Node::Values(values) => Err(DeError(format!("{} `{}` is given {} times, expected a single value", self.kind, self.name, values.len()))),
+            Node::Map(_) => Err(DeError(format!("{} `{}` has nested keys, expected a single value", self.kind, self.name))),
+        }
+    }
+}
+
+macro_rules! forward_to_value {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+                self.value()?.$method(visitor)
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for NodeDeserializer<'de> {
+    type Error = DeError;
+
+    forward_to_value! {
+        deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64 deserialize_i128
+        deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64 deserialize_u128
+        deserialize_f32 deserialize_f64
+        deserialize_char deserialize_str deserialize_string
+        deserialize_bytes deserialize_byte_buf deserialize_identifier
+    }
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        match self.node {
+            Node::Values(values) if values.len() == 1 => visitor.visit_borrowed_str(values[0]),
+            Node::Values(_) => self.deserialize_seq(visitor),
+            Node::Map(_) => self.deserialize_map(visitor),
+        }
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        let value = self.value()?;
+        if value.value.is_empty() {
+            // A bare `?flag` switches the flag on
+            return visitor.visit_bool(true);
+        }
+        value.deserialize_bool(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, DeError> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, DeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        let (kind, name) = (self.kind, self.name);
+        let items: Vec<NodeDeserializer<'de>> = match self.node {
+            Node::Values(values) => values.into_iter()
+                .map(|value| NodeDeserializer { kind, name, node: Node::Values(vec![value]) })
+                .collect(),
+            Node::Map(mut entries) => {
+                // `items[0]`, `items[1]`, ... are read in index order
+                if entries.iter().all(|(key, _)| key.parse::<usize>().is_ok()) {
+                    entries.sort_by_key(|(key, _)| key.parse::<usize>().unwrap_or_default());
+                }
+                entries.into_iter()
+                    .map(|(_, node)| NodeDeserializer { kind, name, node })
+                    .collect()
+            }
+        };
+        visitor.visit_seq(NodeSeq { items: items.into_iter() })
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, DeError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(self, _name: &'static str, _len: usize, visitor: V) -> Result<V::Value, DeError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        match self.node {
+            Node::Map(entries) => visitor.visit_map(NodeMap { kind: self.kind, entries: entries.into_iter(), value: None }),
+            Node::Values(_) => Err(DeError(format!("{} `{1}` has a value, expected nested keys like `{1}[key]`", self.kind, self.name))),
+        }
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        self.value()?.deserialize_enum(name, variants, visitor)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        visitor.visit_unit()
+    }
+}
+
+struct NodeSeq<'de> {
+    items: std::vec::IntoIter<NodeDeserializer<'de>>,
+}
+
+impl<'de> SeqAccess<'de> for NodeSeq<'de> {
+    type Error = DeError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>, DeError> {
+        self.items.next().map(|item| seed.deserialize(item)).transpose()
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.items.len())
+    }
+}
+
+struct NodeMap<'de> {
+    kind: &'static str,
+    entries: std::vec::IntoIter<(&'de str, Node<'de>)>,
+    value: Option<NodeDeserializer<'de>>,
+}
+
+impl<'de> MapAccess<'de> for NodeMap<'de> {
+    type Error = DeError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, DeError> {
+        match self.entries.next() {
+            Some((name, node)) => {
+                self.value = Some(NodeDeserializer { kind: self.kind, name, node });
+                seed.deserialize(name.into_deserializer()).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, DeError> {
+        let value = self.value.take().ok_or_else(|| de::Error::custom("value requested before key"))?;
+        seed.deserialize(value)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.entries.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use serde::Deserialize;
+
+    use super::*;
+
+    fn pairs(query: &str) -> Vec<(String, String)> {
+        form_urlencoded::parse(query.as_bytes()).into_owned().collect()
+    }
+
+    fn from_query<T: for<'de> Deserialize<'de>>(query: &str) -> Result<T, DeError> {
+        T::deserialize(QueryDeserializer::new(&pairs(query)))
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Search {
+        q: String,
+        page: Option<u32>,
+        #[serde(default)]
+        tags: Vec<String>,
+        #[serde(default)]
+        exact: bool,
+    }
+
+    #[test]
+    fn values_follow_the_field_types() {
+        let search: Search = from_query("q=01234&page=2").unwrap();
+        assert_eq!(search, Search { q: "01234".to_string(), page: Some(2), tags: vec![], exact: false });
+    }
+
+    #[test]
+    fn repeated_and_appended_keys_fill_vecs() {
+        let search: Search = from_query("q=a&tags=x&tags=y").unwrap();
+        assert_eq!(search.tags, ["x", "y"]);
+
+        let search: Search = from_query("q=a&tags[]=x&tags[]=y").unwrap();
+        assert_eq!(search.tags, ["x", "y"]);
+    }
+
+    #[test]
+    fn valueless_keys_are_true_flags() {
+        let search: Search = from_query("q=a&exact").unwrap();
+        assert!(search.exact);
+    }
+
+    #[test]
+    fn bracketed_keys_nest() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Filter {
+            filter: HashMap<String, HashMap<String, String>>,
+        }
+
+        let filter: Filter = from_query("filter[a][b]=1&filter[a][c]=2").unwrap();
+        assert_eq!(filter.filter["a"]["b"], "1");
+        assert_eq!(filter.filter["a"]["c"], "2");
+    }
+
+    #[test]
+    fn indexed_keys_are_read_in_order() {
+        #[derive(Debug, Deserialize)]
+        struct Items {
+            items: Vec<u32>,
+        }
+
+        let items: Items = from_query("items[1]=20&items[0]=10").unwrap();
+        assert_eq!(items.items, [10, 20]);
+    }
+
+    #[test]
+    fn reports_invalid_and_conflicting_values() {
+        let error = from_query::<Search>("q=a&page=two").unwrap_err();
+        assert!(error.to_string().starts_with("query parameter `page` = `two` is not a valid u32"), "{error}");
+
+        let error = from_query::<Search>("q=a&q=b").unwrap_err();
+        assert_eq!(error.to_string(), "query parameter `q` is given 2 times, expected a single value");
+
+        let error = from_query::<Search>("q=a&q[x]=b").unwrap_err();
+        assert_eq!(error.to_string(), "key `q[x]` is given both a value and nested keys");
+    }
+
+    #[test]
+    fn forms_name_fields_in_errors() {
+        let error = Search::deserialize(QueryDeserializer::form(&pairs("q=a&page=x"))).unwrap_err();
+        assert!(error.to_string().starts_with("form field `page`"), "{error}");
+    }
+}
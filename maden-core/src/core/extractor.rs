@@ -1,64 +1,170 @@
-use std::collections::HashMap;
+use std::ops::Deref;
+use std::sync::Arc;
 use serde::de::DeserializeOwned;
 use crate::core::http::Request;
 use crate::core::error::MadenError;
+use crate::core::de::{PathDeserializer, QueryDeserializer};
+use crate::core::guard::{essence, header};
 
-/// Trait for extracting data from HTTP requests
-pub trait FromRequest: Sized {
-    async fn from_request(req: &Request) -> Result<Self, MadenError>;
+/// Trait for extracting data from the request head (path, query, headers, state)
+/// without consuming the body. Any handler argument can use it.
+pub trait FromRequestParts: Sized {
+    fn from_request_parts(req: &Request) -> impl Future<Output = Result<Self, MadenError>> + Send;
+}
+
+/// Trait for extracting data from the whole request, including its body.
+/// Only the last handler argument is extracted this way.
+///
+/// `M` only distinguishes the blanket impl for `FromRequestParts` types and
+/// can be ignored by implementors.
+pub trait FromRequest<M = ViaRequest>: Sized {
+    fn from_request(req: Request) -> impl Future<Output = Result<Self, MadenError>> + Send;
+}
+
+#[doc(hidden)]
+pub enum ViaRequest {}
+
+#[doc(hidden)]
+pub enum ViaParts {}
+
+impl<T> FromRequest<ViaParts> for T
+where
+    T: FromRequestParts,
+{
+    async fn from_request(req: Request) -> Result<Self, MadenError> {
+        T::from_request_parts(&req).await
+    }
+}
+
+/// Optional extraction: failures become `None`
+impl<T> FromRequestParts for Option<T>
+where
+    T: FromRequestParts,
+{
+    async fn from_request_parts(req: &Request) -> Result<Self, MadenError> {
+        Ok(T::from_request_parts(req).await.ok())
+    }
+}
+
+impl<T> FromRequest for Option<T>
+where
+    T: FromRequest,
+{
+    async fn from_request(req: Request) -> Result<Self, MadenError> {
+        Ok(T::from_request(req).await.ok())
+    }
+}
+
+/// Fallible extraction: the handler receives the extraction error instead of it being returned
+impl<T> FromRequestParts for Result<T, MadenError>
+where
+    T: FromRequestParts,
+{
+    async fn from_request_parts(req: &Request) -> Result<Self, MadenError> {
+        Ok(T::from_request_parts(req).await)
+    }
+}
+
+impl<T> FromRequest for Result<T, MadenError>
+where
+    T: FromRequest,
+{
+    async fn from_request(req: Request) -> Result<Self, MadenError> {
+        Ok(T::from_request(req).await)
+    }
+}
+
+/// The whole request, body included
+impl FromRequest for Request {
+    async fn from_request(mut req: Request) -> Result<Self, MadenError> {
+        req.read_body().await?;
+        Ok(req)
+    }
 }
 
 /// Extract path parameters
+///
+/// Structs are filled by parameter name and tuples by position, e.g.
+/// `Path<(u32, String)>` for `/users/{id}/posts/{slug}`. A single parameter
+/// can be read directly as a number, bool, string or unit enum.
 pub struct Path<T>(pub T);
 
-impl<T> FromRequest for Path<T>
+impl<T> FromRequestParts for Path<T>
 where
     T: DeserializeOwned,
 {
-    async fn from_request(req: &Request) -> Result<Self, MadenError> {
-        let json_value = serde_json::to_value(&req.path_params)
-            .map_err(|e| MadenError::bad_request(format!("Failed to serialize path params: {}", e)))?;
-        
-        let extracted = serde_json::from_value(json_value)
+    async fn from_request_parts(req: &Request) -> Result<Self, MadenError> {
+        let extracted = T::deserialize(PathDeserializer::new(req.path_params.as_slice()))
             .map_err(|e| MadenError::bad_request(format!("Failed to extract path params: {}", e)))?;
-        
+
         Ok(Path(extracted))
     }
 }
 
+/// Extract host parameters
+///
+/// Works like [`Path`] for the `{name}` labels of the route's host, e.g.
+/// `Host<String>` for `host = "{tenant}.example.com"`.
+pub struct Host<T>(pub T);
+
+impl<T> FromRequestParts for Host<T>
+where
+    T: DeserializeOwned,
+{
+    async fn from_request_parts(req: &Request) -> Result<Self, MadenError> {
+        let extracted = T::deserialize(PathDeserializer::host(req.host_params.as_slice()))
+            .map_err(|e| MadenError::bad_request(format!("Failed to extract host params: {}", e)))?;
+
+        Ok(Host(extracted))
+    }
+}
+
 /// Extract query parameters
+///
+/// Values are parsed according to the field types of `T`; repeated keys fill
+/// `Vec` fields and bracketed keys like `filter[status]` fill nested structs.
 pub struct Query<T>(pub T);
 
-impl<T> FromRequest for Query<T>
+impl<T> FromRequestParts for Query<T>
 where
     T: DeserializeOwned,
 {
-    async fn from_request(req: &Request) -> Result<Self, MadenError> {
-        // Convert query parameters to a format that serde can handle
-        let mut converted_params = std::collections::HashMap::new();
-        
-        for (key, value) in &req.query_params {
-            // Try to parse as number first, then fall back to string
-            if let Ok(num) = value.parse::<i64>() {
-                converted_params.insert(key.clone(), serde_json::Value::Number(serde_json::Number::from(num)));
-            } else if let Ok(num) = value.parse::<f64>() {
-                converted_params.insert(key.clone(), serde_json::Value::Number(serde_json::Number::from_f64(num).unwrap_or(serde_json::Number::from(0))));
-            } else if value == "true" || value == "false" {
-                converted_params.insert(key.clone(), serde_json::Value::Bool(value == "true"));
-            } else {
-                converted_params.insert(key.clone(), serde_json::Value::String(value.clone()));
-            }
-        }
-        
-        let json_value = serde_json::Value::Object(converted_params.into_iter().collect());
-        
-        let extracted = serde_json::from_value(json_value)
+    async fn from_request_parts(req: &Request) -> Result<Self, MadenError> {
+        let extracted = T::deserialize(QueryDeserializer::new(req.query_params.as_slice()))
             .map_err(|e| MadenError::bad_request(format!("Failed to extract query params: {}", e)))?;
-        
+
         Ok(Query(extracted))
     }
 }
 
+/// Extract shared application state registered with `Maden::with_state` or `Maden::manage`
+pub struct State<T>(pub Arc<T>);
+
+impl<T> Deref for State<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> Clone for State<T> {
+    fn clone(&self) -> Self {
+        State(self.0.clone())
+    }
+}
+
+impl<T> FromRequestParts for State<T>
+where
+    T: Send + Sync + 'static,
+{
+    async fn from_request_parts(req: &Request) -> Result<Self, MadenError> {
+        req.state.get::<T>()
+            .map(State)
+            .ok_or_else(|| MadenError::internal_server_error(format!("State `{}` is not managed by Maden", std::any::type_name::<T>())))
+    }
+}
+
 /// Extract JSON body
 pub struct Json<T>(pub T);
 
@@ -66,8 +172,9 @@ impl<T> FromRequest for Json<T>
 where
     T: DeserializeOwned,
 {
-    async fn from_request(req: &Request) -> Result<Self, MadenError> {
-        let body_str = String::from_utf8(req.body.clone())
+    async fn from_request(mut req: Request) -> Result<Self, MadenError> {
+        req.read_body().await?;
+        let body_str = String::from_utf8(req.body)
             .map_err(|e| MadenError::bad_request(format!("Invalid UTF-8 in request body: {}", e)))?;
         
         let extracted = serde_json::from_str(&body_str)
@@ -77,45 +184,42 @@ where
     }
 }
 
-// Implement FromRequest for primitive types (for path parameters)
-impl FromRequest for String {
-    async fn from_request(req: &Request) -> Result<Self, MadenError> {
-        // This will be handled by the macro for specific path parameters
-        Err(MadenError::internal_server_error("String extraction should be handled by macro"))
-    }
-}
-
-impl FromRequest for u32 {
-    async fn from_request(req: &Request) -> Result<Self, MadenError> {
-        Err(MadenError::internal_server_error("u32 extraction should be handled by macro"))
-    }
-}
+/// Extract an `application/x-www-form-urlencoded` body
+///
+/// Fields are decoded like [`Query`]: by type, with repeated and bracketed
+/// keys. Other content types are rejected with `415 Unsupported Media Type`.
+pub struct Form<T>(pub T);
 
-impl FromRequest for u64 {
-    async fn from_request(req: &Request) -> Result<Self, MadenError> {
-        Err(MadenError::internal_server_error("u64 extraction should be handled by macro"))
-    }
-}
+impl<T> FromRequest for Form<T>
+where
+    T: DeserializeOwned,
+{
+    async fn from_request(mut req: Request) -> Result<Self, MadenError> {
+        let content_type = header(&req, "Content-Type").map(essence);
+        if content_type.as_deref() != Some("application/x-www-form-urlencoded") {
+            return Err(MadenError::unsupported_media_type(format!(
+                "Expected an `application/x-www-form-urlencoded` body, got `{}`",
+                content_type.as_deref().unwrap_or("no content type"),
+            )));
+        }
+        req.read_body().await?;
 
-impl FromRequest for i32 {
-    async fn from_request(req: &Request) -> Result<Self, MadenError> {
-        Err(MadenError::internal_server_error("i32 extraction should be handled by macro"))
-    }
-}
+        let pairs: Vec<(String, String)> = form_urlencoded::parse(&req.body).into_owned().collect();
+        let extracted = T::deserialize(QueryDeserializer::form(&pairs))
+            .map_err(|e| MadenError::bad_request(format!("Failed to extract form fields: {}", e)))?;
 
-impl FromRequest for i64 {
-    async fn from_request(req: &Request) -> Result<Self, MadenError> {
-        Err(MadenError::internal_server_error("i64 extraction should be handled by macro"))
+        Ok(Form(extracted))
     }
 }
 
-// Helper function to extract a single path parameter by name
+// Helper function to extract a single path or host parameter by name
 pub fn extract_path_param<T>(req: &Request, param_name: &str) -> Result<T, MadenError>
 where
     T: std::str::FromStr,
     T::Err: std::fmt::Display,
 {
     let param_value = req.path_params.get(param_name)
+        .or_else(|| req.host_params.get(param_name))
         .ok_or_else(|| MadenError::bad_request(format!("Missing path parameter: {}", param_name)))?;
     
     param_value.parse::<T>()
@@ -0,0 +1,129 @@
+use hyper::StatusCode;
+
+use crate::core::http::Request;
+
+/// A custom predicate a request must satisfy for a route to be selected.
+///
+/// Guards see the request head: method, path, headers, query and state. The
+/// body is only read once a route has been chosen. When a guard rejects a
+/// request, the next candidate route for the same method and path is tried.
+///
+/// Plain functions and closures taking `&Request` are guards too:
+///
+/// ```ignore
+/// fn internal_only(req: &Request) -> bool {
+///     req.headers.contains_key("x-internal")
+/// }
+///
+/// #[get("/metrics", guard = internal_only)]
+/// ```
+pub trait Guard: Send + Sync + 'static {
+    fn check(&self, req: &Request) -> bool;
+
+    /// Status answered when no route accepts the request and this guard
+    /// rejected it. `None` lets the request fall through to the fallback or 404.
+    fn rejection(&self) -> Option<StatusCode> {
+        None
+    }
+}
+
+impl<F> Guard for F
+where
+    F: Fn(&Request) -> bool + Send + Sync + 'static,
+{
+    fn check(&self, req: &Request) -> bool {
+        self(req)
+    }
+}
+
+/// Looks up a header by name, ignoring ASCII case.
+pub(crate) fn header<'a>(req: &'a Request, name: &str) -> Option<&'a str> {
+    req.headers.iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.as_str())
+}
+
+/// The `type/subtype` part of a media type, lowercased and without parameters.
+pub(crate) fn essence(media_type: &str) -> String {
+    media_type.split(';').next().unwrap_or_default().trim().to_ascii_lowercase()
+}
+
+/// Whether `range` (which may use `*` wildcards) covers `media_type`.
+fn media_range_matches(range: &str, media_type: &str) -> bool {
+    let (range_type, range_subtype) = range.split_once('/').unwrap_or((range, ""));
+    let (kind, subtype) = media_type.split_once('/').unwrap_or((media_type, ""));
+    (range_type == "*" || range_type == kind) && (range_subtype == "*" || range_subtype == subtype)
+}
+
+/// Requires the request body to have a given `Content-Type`, answering
+/// `415 Unsupported Media Type` otherwise. Set by `consumes = "..."`.
+#[derive(Clone, Debug)]
+pub struct Consumes(String);
+
+impl Consumes {
+    pub fn new(media_type: &str) -> Self {
+        Self(essence(media_type))
+    }
+}
+
+impl Guard for Consumes {
+    fn check(&self, req: &Request) -> bool {
+        header(req, "Content-Type").is_some_and(|content_type| media_range_matches(&self.0, &essence(content_type)))
+    }
+
+    fn rejection(&self) -> Option<StatusCode> {
+        Some(StatusCode::UNSUPPORTED_MEDIA_TYPE)
+    }
+}
+
+/// Requires the client to accept a given response media type, answering
+/// `406 Not Acceptable` otherwise. Set by `produces = "..."`.
+///
+/// Requests without an `Accept` header accept anything.
+#[derive(Clone, Debug)]
+pub struct Produces(String);
+
+impl Produces {
+    pub fn new(media_type: &str) -> Self {
+        Self(essence(media_type))
+    }
+}
+
+impl Guard for Produces {
+    fn check(&self, req: &Request) -> bool {
+        let Some(accept) = header(req, "Accept") else {
+            return true;
+        };
+        accept.split(',').any(|range| {
+            let mut params = range.split(';');
+            let media_range = params.next().unwrap_or_default().trim().to_ascii_lowercase();
+            let refused = params
+                .filter_map(|param| param.trim().strip_prefix("q="))
+                .any(|q| q.trim().parse::<f32>().is_ok_and(|q| q <= 0.0));
+            !refused && media_range_matches(&media_range, &self.0)
+        })
+    }
+
+    fn rejection(&self) -> Option<StatusCode> {
+        Some(StatusCode::NOT_ACCEPTABLE)
+    }
+}
+
+/// Requires a request header to have an exact value. Set by `header("Name" = "value")`.
+#[derive(Clone, Debug)]
+pub struct HeaderGuard {
+    name: String,
+    value: String,
+}
+
+impl HeaderGuard {
+    pub fn new(name: impl Into<String>, value: impl Into<String>) -> Self {
+        Self { name: name.into(), value: value.into() }
+    }
+}
+
+impl Guard for HeaderGuard {
+    fn check(&self, req: &Request) -> bool {
+        header(req, &self.name).is_some_and(|value| value.trim() == self.value)
+    }
+}
@@ -1,6 +1,13 @@
 use std::collections::HashMap;
-use hyper::body::Bytes;
-use http_body_util::Full;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use cookie::{Cookie, CookieJar, Key};
+use hyper::body::{Bytes, Incoming};
+use http_body_util::{BodyExt, Full};
+
+use crate::core::error::MadenError;
+use crate::core::state::StateMap;
+use crate::core::url::RouteUrls;
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum HttpMethod {
@@ -11,31 +18,188 @@ pub enum HttpMethod {
     Patch,
     Options,
     Head,
+    Trace,
+    Connect,
+    /// Extension method without a dedicated variant, e.g. `PURGE`.
+    Custom(String),
 }
 
 impl HttpMethod {
-    pub fn from_hyper(method: &hyper::Method) -> Option<Self> {
+    pub fn from_hyper(method: &hyper::Method) -> Self {
         match *method {
-            hyper::Method::GET => Some(HttpMethod::Get),
-            hyper::Method::POST => Some(HttpMethod::Post),
-            hyper::Method::PUT => Some(HttpMethod::Put),
-            hyper::Method::DELETE => Some(HttpMethod::Delete),
-            hyper::Method::PATCH => Some(HttpMethod::Patch),
-            hyper::Method::OPTIONS => Some(HttpMethod::Options),
-            hyper::Method::HEAD => Some(HttpMethod::Head),
-            _ => None,
+            hyper::Method::GET => HttpMethod::Get,
+            hyper::Method::POST => HttpMethod::Post,
+            hyper::Method::PUT => HttpMethod::Put,
+            hyper::Method::DELETE => HttpMethod::Delete,
+            hyper::Method::PATCH => HttpMethod::Patch,
+            hyper::Method::OPTIONS => HttpMethod::Options,
+            hyper::Method::HEAD => HttpMethod::Head,
+            hyper::Method::TRACE => HttpMethod::Trace,
+            hyper::Method::CONNECT => HttpMethod::Connect,
+            _ => HttpMethod::Custom(method.as_str().to_string()),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            HttpMethod::Get => "GET",
+            HttpMethod::Post => "POST",
+            HttpMethod::Put => "PUT",
+            HttpMethod::Delete => "DELETE",
+            HttpMethod::Patch => "PATCH",
+            HttpMethod::Options => "OPTIONS",
+            HttpMethod::Head => "HEAD",
+            HttpMethod::Trace => "TRACE",
+            HttpMethod::Connect => "CONNECT",
+            HttpMethod::Custom(method) => method,
         }
     }
 }
 
+/// Parameters captured by the matched route, in the order they appear in
+/// its path or host, so `Path<(A, B)>` can read them by position.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PathParams(Vec<(String, String)>);
+
+impl PathParams {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&String> {
+        self.0.iter().find(|(key, _)| key == name).map(|(_, value)| value)
+    }
+
+    /// Sets `name`, replacing an earlier value while keeping its position.
+    pub fn insert(&mut self, name: impl Into<String>, value: impl Into<String>) {
+        let name = name.into();
+        let value = value.into();
+        match self.0.iter_mut().find(|(key, _)| *key == name) {
+            Some((_, existing)) => *existing = value,
+            None => self.0.push((name, value)),
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.0.iter().map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub(crate) fn as_slice(&self) -> &[(String, String)] {
+        &self.0
+    }
+}
+
+impl Extend<(String, String)> for PathParams {
+    fn extend<I: IntoIterator<Item = (String, String)>>(&mut self, iter: I) {
+        for (name, value) in iter {
+            self.insert(name, value);
+        }
+    }
+}
+
+impl FromIterator<(String, String)> for PathParams {
+    fn from_iter<I: IntoIterator<Item = (String, String)>>(iter: I) -> Self {
+        let mut params = PathParams::new();
+        params.extend(iter);
+        params
+    }
+}
+
+/// Decoded `application/x-www-form-urlencoded` pairs of the query string.
+///
+/// Pairs keep their order and repeated keys keep every value; a key without
+/// `=` (e.g. `?flag`) has an empty value.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct QueryParams(Vec<(String, String)>);
+
+impl QueryParams {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Decodes a query string, turning `+` into spaces and resolving percent escapes.
+    pub fn parse(query: &str) -> Self {
+        Self(form_urlencoded::parse(query.as_bytes()).into_owned().collect())
+    }
+
+    /// The first value of `key`.
+    pub fn get(&self, key: &str) -> Option<&String> {
+        self.0.iter().find(|(name, _)| name == key).map(|(_, value)| value)
+    }
+
+    /// Every value of `key`, in order.
+    pub fn get_all<'a>(&'a self, key: &'a str) -> impl Iterator<Item = &'a str> {
+        self.0.iter().filter(move |(name, _)| name == key).map(|(_, value)| value.as_str())
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.0.iter().any(|(name, _)| name == key)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.0.iter().map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub(crate) fn as_slice(&self) -> &[(String, String)] {
+        &self.0
+    }
+}
+
+impl FromIterator<(String, String)> for QueryParams {
+    fn from_iter<I: IntoIterator<Item = (String, String)>>(iter: I) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+/// Request body the server left unread until an extractor asks for it.
+/// Clones of a request share the slot; the first to take the body gets it.
+#[derive(Clone, Default)]
+pub(crate) struct UnreadBody(Arc<Mutex<Option<Incoming>>>);
+
+impl UnreadBody {
+    pub(crate) fn new(body: Incoming) -> Self {
+        Self(Arc::new(Mutex::new(Some(body))))
+    }
+
+    pub(crate) fn take(&self) -> Option<Incoming> {
+        self.0.lock().ok()?.take()
+    }
+}
+
+/// An incoming request.
+///
+/// The server does not read the body up front: the `Request`, `Json` and
+/// `Form` extractors read it into `body`, while `Multipart` streams it.
+/// Middleware that needs `body` calls [`Request::read_body`] first.
 #[derive(Clone)]
 pub struct Request {
     pub method: HttpMethod,
     pub path: String,
     pub headers: HashMap<String, String>,
-    pub path_params: HashMap<String, String>,
-    pub query_params: HashMap<String, String>,
+    pub path_params: PathParams,
+    /// Parameters captured from the host, e.g. `tenant` for `host = "{tenant}.example.com"`.
+    pub host_params: PathParams,
+    pub query_params: QueryParams,
     pub body: Vec<u8>,
+    pub state: Arc<StateMap>,
+    pub(crate) unread_body: UnreadBody,
+    pub(crate) allow: Option<String>,
 }
 
 impl Request {
@@ -43,8 +207,8 @@ impl Request {
         method: HttpMethod,
         path: String,
         headers: HashMap<String, String>,
-        path_params: HashMap<String, String>,
-        query_params: HashMap<String, String>,
+        path_params: PathParams,
+        query_params: QueryParams,
         body: Vec<u8>,
     ) -> Self {
         Self {
@@ -52,15 +216,55 @@ impl Request {
             path,
             headers,
             path_params,
+            host_params: PathParams::new(),
             query_params,
             body,
+            state: Arc::new(StateMap::new()),
+            unread_body: UnreadBody::default(),
+            allow: None,
+        }
+    }
+
+    pub fn with_state(mut self, state: Arc<StateMap>) -> Self {
+        self.state = state;
+        self
+    }
+
+    /// Methods the matched path answers on this host, the same ones the
+    /// server sends in `Allow`. Only set for OPTIONS requests, so handlers
+    /// answering a CORS preflight can check the requested method.
+    pub fn allowed_methods(&self) -> impl Iterator<Item = &str> {
+        self.allow.iter().flat_map(|allow| allow.split(", "))
+    }
+
+    /// Reads a body the server left unread into `body`; does nothing once it has been read.
+    pub async fn read_body(&mut self) -> Result<(), MadenError> {
+        if let Some(body) = self.unread_body.take() {
+            let collected = body.collect().await
+                .map_err(|e| MadenError::bad_request(format!("Failed to read request body: {}", e)))?;
+            self.body = collected.to_bytes().to_vec();
         }
+        Ok(())
+    }
+
+    /// Builds the URL of a named route, see [`RouteUrls::url_for`].
+    pub fn url_for<I, K, V>(&self, name: &str, params: I) -> Result<String, MadenError>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: fmt::Display,
+    {
+        self.state.get::<RouteUrls>()
+            .ok_or_else(|| MadenError::internal_server_error("Route URLs are not available outside a running server"))?
+            .url_for(name, params)
     }
 }
 
 pub struct Response {
     pub status_code: u16,
     pub headers: HashMap<String, String>,
+    /// Cookies to set, each sent as its own `Set-Cookie` header.
+    pub cookies: Vec<Cookie<'static>>,
     pub body: Vec<u8>,
 }
 
@@ -69,11 +273,51 @@ impl Response {
         Self {
             status_code,
             headers: HashMap::new(),
+            cookies: Vec::new(),
             body: Vec::new(),
         }
     }
 
+    /// Sets a cookie, with its attributes given through [`Cookie::build`]:
+    ///
+    /// ```ignore
+    /// response.cookie(Cookie::build(("session", id)).path("/").http_only(true).same_site(SameSite::Lax))
+    /// ```
+    pub fn cookie(mut self, cookie: impl Into<Cookie<'static>>) -> Self {
+        self.cookies.push(cookie.into());
+        self
+    }
+
+    /// Tells the client to delete a cookie. Its path and domain must match the ones it was set with.
+    pub fn remove_cookie(mut self, cookie: impl Into<Cookie<'static>>) -> Self {
+        let mut cookie = cookie.into();
+        cookie.make_removal();
+        self.cookies.push(cookie);
+        self
+    }
+
+    /// Sets a cookie whose value is signed with `key`, so it can be read but not altered by the client.
+    pub fn signed_cookie(self, cookie: impl Into<Cookie<'static>>, key: &Key) -> Self {
+        let mut jar = CookieJar::new();
+        jar.signed_mut(key).add(cookie);
+        self.with_cookies(&jar)
+    }
+
+    /// Sets a cookie whose value is encrypted with `key`, so it can be neither read nor altered by the client.
+    pub fn private_cookie(self, cookie: impl Into<Cookie<'static>>, key: &Key) -> Self {
+        let mut jar = CookieJar::new();
+        jar.private_mut(key).add(cookie);
+        self.with_cookies(&jar)
+    }
+
+    /// Sets every cookie added to or removed from `jar`.
+    pub(crate) fn with_cookies(mut self, jar: &CookieJar) -> Self {
+        self.cookies.extend(jar.delta().cloned());
+        self
+    }
+
     pub fn with_header(mut self, key: &str, value: &str) -> Self {
+        self.headers.retain(|existing, _| !existing.eq_ignore_ascii_case(key));
         self.headers.insert(key.to_string(), value.to_string());
         self
     }
@@ -120,15 +364,25 @@ impl IntoResponse for Response {
 
 
 
+/// Responses with an invalid status or header become a plain 500 instead of
+/// panicking the connection.
 impl From<Response> for hyper::Response<Full<Bytes>> {
     fn from(maden_res: Response) -> Self {
         let mut builder = hyper::Response::builder().status(maden_res.status_code);
         for (key, value) in maden_res.headers {
             builder = builder.header(key, value);
         }
+        for cookie in maden_res.cookies {
+            builder = builder.header(hyper::header::SET_COOKIE, cookie.encoded().to_string());
+        }
         builder
             .body(Full::new(Bytes::from(maden_res.body)))
-            .unwrap()
+            .unwrap_or_else(|e| {
+                maden_log::error!("Invalid response: {}", e);
+                let mut response = hyper::Response::new(Full::new(Bytes::from_static(b"Internal Server Error")));
+                *response.status_mut() = hyper::StatusCode::INTERNAL_SERVER_ERROR;
+                response
+            })
     }
 }
 
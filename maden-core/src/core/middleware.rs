@@ -0,0 +1,80 @@
+use std::{
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+};
+
+use crate::core::http::{Request, Response};
+use crate::core::service::Handler;
+
+type MiddlewareFn = dyn Fn(Request, Next) -> Pin<Box<dyn Future<Output = Response> + Send>> + Send + Sync;
+
+/// A named function wrapped around route handlers.
+///
+/// Middleware receives the request and a [`Next`] that runs the rest of the
+/// chain, so it can act before and after the handler or answer on its own.
+#[derive(Clone)]
+pub struct Middleware {
+    name: String,
+    func: Arc<MiddlewareFn>,
+}
+
+impl Middleware {
+    pub fn from_fn<F, Fut>(name: impl Into<String>, func: F) -> Self
+    where
+        F: Fn(Request, Next) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Response> + Send + 'static,
+    {
+        Self {
+            name: name.into(),
+            func: Arc::new(move |req, next| Box::pin(func(req, next))),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl fmt::Debug for Middleware {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Middleware").field(&self.name).finish()
+    }
+}
+
+/// The remaining middleware chain and the route handler.
+pub struct Next {
+    middleware: Arc<[Middleware]>,
+    index: usize,
+    handler: Arc<Handler>,
+}
+
+impl Next {
+    pub async fn run(mut self, req: Request) -> Response {
+        match self.middleware.get(self.index).map(|current| current.func.clone()) {
+            Some(func) => {
+                self.index += 1;
+                func(req, self).await
+            }
+            None => (self.handler)(req).await,
+        }
+    }
+}
+
+/// Wraps `handler` so that `middleware` runs around it, first entry outermost.
+pub fn apply_middleware(handler: Arc<Handler>, middleware: &[Middleware]) -> Arc<Handler> {
+    if middleware.is_empty() {
+        return handler;
+    }
+
+    let middleware: Arc<[Middleware]> = middleware.into();
+    Arc::new(Box::new(move |req| {
+        let next = Next {
+            middleware: middleware.clone(),
+            index: 0,
+            handler: handler.clone(),
+        };
+        Box::pin(next.run(req))
+    }))
+}
@@ -1,8 +1,19 @@
 // maden-core/src/core/mod.rs
 
 pub mod http;
+pub mod response;
 pub mod server;
+pub mod router;
+pub mod routing;
+pub mod url;
+pub mod state;
+pub mod middleware;
+pub mod guard;
+pub mod private;
 pub mod service;
 pub mod tls;
 pub mod error;
 pub mod extractor;
+pub mod de;
+pub mod multipart;
+pub mod cookies;
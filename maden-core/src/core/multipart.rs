@@ -0,0 +1,297 @@
+use std::{
+    convert::Infallible,
+    path::{Path, PathBuf},
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures::{stream, Stream};
+use http_body_util::BodyExt;
+use hyper::body::Bytes;
+use tempfile::NamedTempFile;
+use tokio::io::AsyncWriteExt;
+
+use crate::core::error::MadenError;
+use crate::core::extractor::FromRequest;
+use crate::core::guard::{essence, header};
+use crate::core::http::Request;
+
+/// Size limits and spooling for the [`Multipart`] extractor, managed as state:
+///
+/// ```ignore
+/// maden.manage(
+///     MultipartConfig::new()
+///         .max_field_size(50 * 1024 * 1024)
+///         .field_limit("avatar", 2 * 1024 * 1024)
+///         .spool_dir("/var/tmp/uploads"),
+/// );
+/// ```
+///
+/// Without one, fields are limited to 16 MiB and the whole body to 64 MiB.
+#[derive(Clone, Debug)]
+pub struct MultipartConfig {
+    max_field_size: u64,
+    max_total_size: u64,
+    field_limits: Vec<(String, u64)>,
+    spool_dir: Option<PathBuf>,
+}
+
+impl Default for MultipartConfig {
+    fn default() -> Self {
+        Self {
+            max_field_size: 16 * 1024 * 1024,
+            max_total_size: 64 * 1024 * 1024,
+            field_limits: Vec::new(),
+            spool_dir: None,
+        }
+    }
+}
+
+impl MultipartConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Largest size in bytes of any single field or file.
+    pub fn max_field_size(mut self, limit: u64) -> Self {
+        self.max_field_size = limit;
+        self
+    }
+
+    /// Largest size in bytes of the whole multipart body.
+    pub fn max_total_size(mut self, limit: u64) -> Self {
+        self.max_total_size = limit;
+        self
+    }
+
+    /// Overrides the field size limit for the field called `name`.
+    pub fn field_limit(mut self, name: impl Into<String>, limit: u64) -> Self {
+        self.field_limits.push((name.into(), limit));
+        self
+    }
+
+    /// Directory [`Field::spool`] writes to, instead of the system temp directory.
+    pub fn spool_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.spool_dir = Some(dir.into());
+        self
+    }
+
+    fn constraints(&self) -> multer::Constraints {
+        let size_limit = self.field_limits.iter().fold(
+            multer::SizeLimit::new()
+                .whole_stream(self.max_total_size)
+                .per_field(self.max_field_size),
+            |size_limit, (name, limit)| size_limit.for_field(name.clone(), *limit),
+        );
+        multer::Constraints::new().size_limit(size_limit)
+    }
+}
+
+/// Extract a `multipart/form-data` body as a stream of fields
+///
+/// The body is read as the fields are, so files are never buffered whole
+/// unless asked for. Other content types are rejected with
+/// `415 Unsupported Media Type`, bodies over the [`MultipartConfig`]
+/// limits with `413 Payload Too Large`.
+///
+/// ```ignore
+/// #[post("/upload")]
+/// async fn upload(mut multipart: Multipart) -> Result<String, MadenError> {
+///     while let Some(field) = multipart.next_field().await? {
+///         if field.file_name().is_some() {
+///             field.spool().await?.persist("uploads/latest").await?;
+///         }
+///     }
+///     Ok("uploaded".to_string())
+/// }
+/// ```
+pub struct Multipart {
+    inner: multer::Multipart<'static>,
+    spool_dir: Option<PathBuf>,
+}
+
+impl Multipart {
+    /// The next field, once the previous one has been read or dropped.
+    pub async fn next_field(&mut self) -> Result<Option<Field>, MadenError> {
+        let field = self.inner.next_field().await.map_err(multipart_error)?;
+        Ok(field.map(|inner| Field { inner, spool_dir: self.spool_dir.clone() }))
+    }
+}
+
+impl FromRequest for Multipart {
+    async fn from_request(req: Request) -> Result<Self, MadenError> {
+        let content_type = header(&req, "Content-Type").unwrap_or_default();
+        if essence(content_type) != "multipart/form-data" {
+            return Err(MadenError::unsupported_media_type(format!(
+                "Expected a `multipart/form-data` body, got `{}`",
+                if content_type.is_empty() { "no content type" } else { content_type },
+            )));
+        }
+        let boundary = multer::parse_boundary(content_type)
+            .map_err(|e| MadenError::bad_request(format!("Invalid multipart content type: {}", e)))?;
+
+        let config = req.state.get::<MultipartConfig>().map(|config| (*config).clone()).unwrap_or_default();
+        let constraints = config.constraints();
+        let inner = match req.unread_body.take() {
+            Some(body) => multer::Multipart::with_constraints(body.into_data_stream(), boundary, constraints),
+            // Requests built outside the server carry their body in `body`
+            None => {
+                let body = Bytes::from(req.body);
+                multer::Multipart::with_constraints(stream::once(async move { Ok::<_, Infallible>(body) }), boundary, constraints)
+            }
+        };
+
+        Ok(Multipart { inner, spool_dir: config.spool_dir })
+    }
+}
+
+/// One field of a multipart body: a form value or an uploaded file.
+///
+/// A field is also a [`Stream`] of its body chunks.
+pub struct Field {
+    inner: multer::Field<'static>,
+    spool_dir: Option<PathBuf>,
+}
+
+impl Field {
+    pub fn name(&self) -> Option<&str> {
+        self.inner.name()
+    }
+
+    /// The file name sent by the client, present for file uploads.
+    pub fn file_name(&self) -> Option<&str> {
+        self.inner.file_name()
+    }
+
+    pub fn content_type(&self) -> Option<&str> {
+        self.inner.content_type().map(|mime| mime.as_ref())
+    }
+
+    /// The next chunk of the field body, or `None` once it has been read.
+    pub async fn chunk(&mut self) -> Result<Option<Bytes>, MadenError> {
+        self.inner.chunk().await.map_err(multipart_error)
+    }
+
+    /// Reads the whole field into memory.
+    pub async fn bytes(self) -> Result<Bytes, MadenError> {
+        self.inner.bytes().await.map_err(multipart_error)
+    }
+
+    /// Reads the whole field into memory as text.
+    pub async fn text(self) -> Result<String, MadenError> {
+        self.inner.text().await.map_err(multipart_error)
+    }
+
+    /// Streams the field into a new file in the spool directory, for uploads
+    /// too large to keep in memory.
+    pub async fn spool(mut self) -> Result<SpooledFile, MadenError> {
+        let dir = self.spool_dir.clone().unwrap_or_else(std::env::temp_dir);
+        // Creating the file is blocking I/O, so it runs off the async workers
+        let (temp, file) = tokio::task::spawn_blocking(move || {
+            let temp = NamedTempFile::new_in(&dir)?;
+            let file = temp.reopen()?;
+            Ok((temp, file))
+        })
+        .await
+        .map_err(std::io::Error::other)
+        .and_then(|created| created)
+        .map_err(spool_error)?;
+        let mut file = tokio::fs::File::from_std(file);
+
+        let mut size = 0;
+        while let Some(chunk) = self.chunk().await? {
+            size += chunk.len() as u64;
+            file.write_all(&chunk).await.map_err(spool_error)?;
+        }
+        file.flush().await.map_err(spool_error)?;
+
+        Ok(SpooledFile {
+            name: self.name().map(str::to_string),
+            file_name: self.file_name().map(str::to_string),
+            content_type: self.content_type().map(str::to_string),
+            size,
+            temp,
+        })
+    }
+}
+
+impl Stream for Field {
+    type Item = Result<Bytes, MadenError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.inner)
+            .poll_next(cx)
+            .map(|chunk| chunk.map(|result| result.map_err(multipart_error)))
+    }
+}
+
+/// A field written to a temporary file by [`Field::spool`].
+///
+/// The file is deleted when this is dropped, unless it is persisted.
+pub struct SpooledFile {
+    name: Option<String>,
+    file_name: Option<String>,
+    content_type: Option<String>,
+    size: u64,
+    temp: NamedTempFile,
+}
+
+impl SpooledFile {
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn file_name(&self) -> Option<&str> {
+        self.file_name.as_deref()
+    }
+
+    pub fn content_type(&self) -> Option<&str> {
+        self.content_type.as_deref()
+    }
+
+    /// Size of the file in bytes.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Path of the temporary file.
+    pub fn path(&self) -> &Path {
+        self.temp.path()
+    }
+
+    /// Moves the file to `path`, copying it when `path` is on another file system.
+    pub async fn persist(self, path: impl AsRef<Path>) -> Result<(), MadenError> {
+        let path = path.as_ref().to_path_buf();
+        let temp = self.temp;
+        tokio::task::spawn_blocking(move || match temp.persist(&path) {
+            Ok(_) => Ok(()),
+            // A rename cannot cross file systems; the temporary file is deleted after copying
+            Err(error) if error.error.kind() == std::io::ErrorKind::CrossesDevices => {
+                std::fs::copy(error.file.path(), &path).map(|_| ())
+            }
+            Err(error) => Err(error.error),
+        })
+        .await
+        .map_err(std::io::Error::other)
+        .and_then(|persisted| persisted)
+        .map_err(|e| MadenError::internal_server_error(format!("Failed to persist spooled file: {}", e)))
+    }
+}
+
+fn multipart_error(error: multer::Error) -> MadenError {
+    match error {
+        multer::Error::FieldSizeExceeded { limit, field_name } => MadenError::payload_too_large(format!(
+            "Multipart field `{}` exceeds the limit of {} bytes",
+            field_name.as_deref().unwrap_or_default(),
+            limit,
+        )),
+        multer::Error::StreamSizeExceeded { limit } => {
+            MadenError::payload_too_large(format!("Multipart body exceeds the limit of {} bytes", limit))
+        }
+        error => MadenError::bad_request(format!("Invalid multipart body: {}", error)),
+    }
+}
+
+fn spool_error(error: std::io::Error) -> MadenError {
+    MadenError::internal_server_error(format!("Failed to spool multipart field: {}", error))
+}
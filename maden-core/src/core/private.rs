@@ -0,0 +1,42 @@
+//! Support items for code generated by `maden-macros`. Not public API.
+
+use std::marker::PhantomData;
+
+/// Resolves a handler instance through `Default` when the type implements it.
+///
+/// Generated code calls `(&&InstanceProbe::<H>::new()).instance()`: method
+/// resolution picks `ViaDefault` when `H: Default` and falls back to
+/// `ViaMissing` otherwise, without requiring the bound up front.
+pub struct InstanceProbe<H>(PhantomData<H>);
+
+impl<H> InstanceProbe<H> {
+    pub fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<H> Default for InstanceProbe<H> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub trait ViaDefault<H> {
+    fn instance(&self) -> Option<H>;
+}
+
+impl<H: Default> ViaDefault<H> for &InstanceProbe<H> {
+    fn instance(&self) -> Option<H> {
+        Some(H::default())
+    }
+}
+
+pub trait ViaMissing<H> {
+    fn instance(&self) -> Option<H>;
+}
+
+impl<H> ViaMissing<H> for InstanceProbe<H> {
+    fn instance(&self) -> Option<H> {
+        None
+    }
+}
@@ -0,0 +1,86 @@
+use hyper::StatusCode;
+use serde::Serialize;
+
+use crate::core::extractor::Json;
+use crate::core::http::{IntoResponse, Response};
+
+/// Respond with an HTML body
+pub struct Html<T>(pub T);
+
+impl IntoResponse for () {
+    fn into_response(self) -> Response {
+        Response::new(200)
+    }
+}
+
+impl IntoResponse for String {
+    fn into_response(self) -> Response {
+        Response::new(200).text(&self)
+    }
+}
+
+impl IntoResponse for &'static str {
+    fn into_response(self) -> Response {
+        Response::new(200).text(self)
+    }
+}
+
+impl<T> IntoResponse for Json<T>
+where
+    T: Serialize,
+{
+    fn into_response(self) -> Response {
+        Response::new(200).json(self.0)
+    }
+}
+
+impl<T> IntoResponse for Html<T>
+where
+    T: AsRef<str>,
+{
+    fn into_response(self) -> Response {
+        Response::new(200).html(self.0.as_ref())
+    }
+}
+
+impl<T> IntoResponse for (StatusCode, T)
+where
+    T: IntoResponse,
+{
+    fn into_response(self) -> Response {
+        let (status, body) = self;
+        let mut response = body.into_response();
+        response.status_code = status.as_u16();
+        response
+    }
+}
+
+impl<H, K, V, T> IntoResponse for (StatusCode, H, T)
+where
+    H: IntoIterator<Item = (K, V)>,
+    K: AsRef<str>,
+    V: AsRef<str>,
+    T: IntoResponse,
+{
+    fn into_response(self) -> Response {
+        let (status, headers, body) = self;
+        let mut response = (status, body).into_response();
+        for (key, value) in headers {
+            response = response.with_header(key.as_ref(), value.as_ref());
+        }
+        response
+    }
+}
+
+impl<T, E> IntoResponse for Result<T, E>
+where
+    T: IntoResponse,
+    E: IntoResponse,
+{
+    fn into_response(self) -> Response {
+        match self {
+            Ok(value) => value.into_response(),
+            Err(err) => err.into_response(),
+        }
+    }
+}
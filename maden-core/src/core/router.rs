@@ -0,0 +1,689 @@
+use std::{
+    fmt,
+    panic::Location,
+    sync::Arc,
+};
+
+use maden_pattern::{ParamConstraint, PathParam, PathPattern};
+use percent_encoding::percent_decode_str;
+
+use crate::core::guard::{Consumes, Guard, HeaderGuard, Produces};
+use crate::core::http::{HttpMethod, PathParams, QueryParams, Request};
+use crate::core::middleware::{apply_middleware, Middleware};
+use crate::core::service::Handler;
+use crate::core::state::StateMap;
+
+/// Required query keys (and optionally values) for a route, parsed from
+/// the `query = "..."` route argument, e.g. `"action=export&verbose"`.
+///
+/// Keys and values are decoded like the request query string, so a bare
+/// `verbose` is satisfied by `?verbose`, `?verbose=` or `?verbose=1`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QueryConstraint {
+    pairs: Vec<(String, Option<String>)>,
+}
+
+impl QueryConstraint {
+    pub fn parse(query: &str) -> Self {
+        let mut pairs: Vec<(String, Option<String>)> = query
+            .trim_start_matches('?')
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .filter_map(|pair| {
+                let (key, value) = form_urlencoded::parse(pair.as_bytes()).next()?;
+                Some((key.into_owned(), pair.contains('=').then(|| value.into_owned())))
+            })
+            .collect();
+        pairs.sort();
+        pairs.dedup();
+
+        Self { pairs }
+    }
+
+    /// Required keys with their required value, if any.
+    pub(crate) fn pairs(&self) -> &[(String, Option<String>)] {
+        &self.pairs
+    }
+
+    /// A `key=value` pair matches when any value of `key` equals `value`.
+    pub fn matches(&self, query_params: &QueryParams) -> bool {
+        self.pairs.iter().all(|(key, expected)| match expected {
+            Some(expected) => query_params.get_all(key).any(|actual| actual == expected),
+            None => query_params.contains_key(key),
+        })
+    }
+
+    /// Routes with more `key=value` pairs win, then routes with more required keys.
+    fn specificity(&self) -> (usize, usize) {
+        let valued = self.pairs.iter().filter(|(_, value)| value.is_some()).count();
+        (valued, self.pairs.len() - valued)
+    }
+
+    /// Two constraints are disjoint when they require different values for the same key,
+    /// so no request can satisfy both.
+    fn is_disjoint(&self, other: &Self) -> bool {
+        self.pairs.iter().any(|(key, value)| {
+            value.is_some() && other.pairs.iter().any(|(other_key, other_value)| {
+                other_key == key && other_value.is_some() && other_value != value
+            })
+        })
+    }
+}
+
+impl fmt::Display for QueryConstraint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pairs: Vec<String> = self.pairs.iter()
+            .map(|(key, value)| match value {
+                Some(value) => format!("{key}={value}"),
+                None => key.clone(),
+            })
+            .collect();
+        write!(f, "{}", pairs.join("&"))
+    }
+}
+
+/// Host a route is restricted to, parsed from the `host = "..."` route argument.
+///
+/// Each dot-separated label is either matched literally (ignoring ASCII case)
+/// or, written as `{name}`, captured as a parameter: `{tenant}.example.com`
+/// matches `acme.example.com` with `tenant = "acme"`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HostPattern {
+    labels: Vec<HostLabel>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum HostLabel {
+    Exact(String),
+    Param(String),
+}
+
+impl HostPattern {
+    pub fn parse(pattern: &str) -> Self {
+        let labels = pattern
+            .trim_end_matches('.')
+            .split('.')
+            .map(|label| match label.strip_prefix('{').and_then(|label| label.strip_suffix('}')) {
+                Some(name) => HostLabel::Param(name.to_string()),
+                None => HostLabel::Exact(label.to_ascii_lowercase()),
+            })
+            .collect();
+
+        Self { labels }
+    }
+
+    /// Matches a request host (without port), returning the captured parameters.
+    pub fn matches(&self, host: &str) -> Option<Vec<(String, String)>> {
+        let host = host.trim_end_matches('.');
+        if host.split('.').count() != self.labels.len() {
+            return None;
+        }
+
+        let mut params = Vec::new();
+        for (label, actual) in self.labels.iter().zip(host.split('.')) {
+            match label {
+                HostLabel::Exact(expected) if expected.eq_ignore_ascii_case(actual) => {}
+                HostLabel::Exact(_) => return None,
+                HostLabel::Param(_) if actual.is_empty() => return None,
+                HostLabel::Param(name) => params.push((name.clone(), actual.to_ascii_lowercase())),
+            }
+        }
+        Some(params)
+    }
+
+    pub fn param_names(&self) -> impl Iterator<Item = &str> {
+        self.labels.iter().filter_map(|label| match label {
+            HostLabel::Param(name) => Some(name.as_str()),
+            HostLabel::Exact(_) => None,
+        })
+    }
+
+    /// Whether no host can match both patterns: they differ in length or in
+    /// a literal label. Parameter names do not matter.
+    fn is_disjoint(&self, other: &HostPattern) -> bool {
+        self.labels.len() != other.labels.len()
+            || self.labels.iter().zip(&other.labels).any(|pair| matches!(pair, (HostLabel::Exact(a), HostLabel::Exact(b)) if a != b))
+    }
+
+    /// Hosts with more literal labels win over wildcard ones.
+    fn specificity(&self) -> usize {
+        self.labels.iter().filter(|label| matches!(label, HostLabel::Exact(_))).count()
+    }
+}
+
+impl fmt::Display for HostPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let labels: Vec<String> = self.labels.iter()
+            .map(|label| match label {
+                HostLabel::Exact(label) => label.clone(),
+                HostLabel::Param(name) => format!("{{{name}}}"),
+            })
+            .collect();
+        write!(f, "{}", labels.join("."))
+    }
+}
+
+/// A route registered on `Maden`, before it is compiled into the router.
+#[derive(Clone)]
+pub struct Route {
+    pub method: HttpMethod,
+    pub path: String,
+    pub query: Option<QueryConstraint>,
+    pub host: Option<HostPattern>,
+    pub guards: Vec<Arc<dyn Guard>>,
+    pub handler: Arc<Handler>,
+    pub name: Option<String>,
+    /// State of the nested app this route came from, layered over the root state.
+    pub state: Option<StateMap>,
+    /// Rust path of the handler function, when known.
+    pub handler_name: Option<String>,
+    /// Where the route was registered.
+    pub location: &'static Location<'static>,
+    pub tags: Vec<String>,
+    pub middleware: Vec<Middleware>,
+    pub success_status: Option<u16>,
+    pub response_headers: Vec<(String, String)>,
+    pub priority: i32,
+}
+
+impl Route {
+    #[track_caller]
+    pub fn new(method: HttpMethod, path: &str, query: Option<QueryConstraint>, handler: Handler) -> Self {
+        Self {
+            method,
+            path: path.to_string(),
+            query,
+            host: None,
+            guards: Vec::new(),
+            handler: Arc::new(handler),
+            name: None,
+            state: None,
+            handler_name: None,
+            location: Location::caller(),
+            tags: Vec::new(),
+            middleware: Vec::new(),
+            success_status: None,
+            response_headers: Vec::new(),
+            priority: 0,
+        }
+    }
+
+    /// Name used to build this route's URL with `url_for`.
+    pub fn name(&mut self, name: impl Into<String>) -> &mut Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Restricts the route to requests whose `Host` matches `pattern`.
+    pub fn host(&mut self, pattern: &str) -> &mut Self {
+        self.host = Some(HostPattern::parse(pattern));
+        self
+    }
+
+    /// Only selects this route for requests the guard accepts.
+    ///
+    /// Guards are opaque, so guarded routes are never reported as ambiguous.
+    /// Of two equally specific routes, the one with the higher [`Route::priority`]
+    /// is tried first, then the one registered first in the source (by file,
+    /// line and column).
+    pub fn guard(&mut self, guard: impl Guard) -> &mut Self {
+        self.guards.push(Arc::new(guard));
+        self
+    }
+
+    /// Only selects this route for request bodies of `media_type`.
+    pub fn consumes(&mut self, media_type: &str) -> &mut Self {
+        self.guard(Consumes::new(media_type))
+    }
+
+    /// Only selects this route for clients accepting `media_type`.
+    pub fn produces(&mut self, media_type: &str) -> &mut Self {
+        self.guard(Produces::new(media_type))
+    }
+
+    /// Only selects this route when the request header `name` equals `value`.
+    pub fn require_header(&mut self, name: impl Into<String>, value: impl Into<String>) -> &mut Self {
+        self.guard(HeaderGuard::new(name, value))
+    }
+
+    pub fn handler_name(&mut self, handler_name: impl Into<String>) -> &mut Self {
+        self.handler_name = Some(handler_name.into());
+        self
+    }
+
+    pub fn tag(&mut self, tag: impl Into<String>) -> &mut Self {
+        self.tags.push(tag.into());
+        self
+    }
+
+    /// Adds middleware around this route; earlier middleware runs first.
+    pub fn middleware(&mut self, middleware: Middleware) -> &mut Self {
+        self.middleware.push(middleware);
+        self
+    }
+
+    /// Tries this route before routes of lower priority on the same method and
+    /// path, whatever their host, path and query constraints. The default is 0.
+    pub fn priority(&mut self, priority: i32) -> &mut Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Status used instead of `200 OK` when the handler succeeds.
+    pub fn status(&mut self, status: u16) -> &mut Self {
+        self.success_status = Some(status);
+        self
+    }
+
+    /// Header added to every successful (2xx) response of this route.
+    pub fn response_header(&mut self, key: impl Into<String>, value: impl Into<String>) -> &mut Self {
+        self.response_headers.push((key.into(), value.into()));
+        self
+    }
+
+    pub fn content_type(&mut self, content_type: impl Into<String>) -> &mut Self {
+        self.response_header("Content-Type", content_type)
+    }
+
+    /// The handler with the declared success status and headers applied to its responses.
+    fn success_handler(&self) -> Arc<Handler> {
+        if self.success_status.is_none() && self.response_headers.is_empty() {
+            return self.handler.clone();
+        }
+
+        let handler = self.handler.clone();
+        let status = self.success_status;
+        let headers: Arc<[(String, String)]> = self.response_headers.clone().into();
+        Arc::new(Box::new(move |req| {
+            let handler = handler.clone();
+            let headers = headers.clone();
+            Box::pin(async move {
+                let mut response = handler(req).await;
+                if !(200..300).contains(&response.status_code) {
+                    return response;
+                }
+
+                if let Some(status) = status.filter(|_| response.status_code == 200) {
+                    response.status_code = status;
+                    if status == 204 || status == 304 {
+                        response.body.clear();
+                        response.headers.retain(|key, _| !key.eq_ignore_ascii_case("Content-Length"));
+                    }
+                }
+                for (key, value) in headers.iter() {
+                    response = response.with_header(key, value);
+                }
+                response
+            })
+        }))
+    }
+}
+
+/// A registered route as reported by `Maden::routes`.
+#[derive(Clone, Debug)]
+pub struct RouteInfo {
+    pub method: HttpMethod,
+    pub path: String,
+    pub query: Option<String>,
+    pub host: Option<String>,
+    pub name: Option<String>,
+    pub handler: Option<String>,
+    pub location: &'static Location<'static>,
+    pub tags: Vec<String>,
+    /// Middleware names, outermost first.
+    pub middleware: Vec<String>,
+}
+
+impl Route {
+    pub fn info(&self) -> RouteInfo {
+        RouteInfo {
+            method: self.method.clone(),
+            path: self.path.clone(),
+            query: self.query.as_ref().map(ToString::to_string),
+            host: self.host.as_ref().map(ToString::to_string),
+            name: self.name.clone(),
+            handler: self.handler_name.clone(),
+            location: self.location,
+            tags: self.tags.clone(),
+            middleware: self.middleware.iter().map(|middleware| middleware.name().to_string()).collect(),
+        }
+    }
+}
+
+/// Formats routes as an aligned plain-text table, one route per line.
+pub fn route_table(routes: &[RouteInfo]) -> String {
+    let header = ["METHOD", "HOST", "PATH", "NAME", "HANDLER", "TAGS", "MIDDLEWARE", "LOCATION"].map(String::from);
+    let rows: Vec<[String; 8]> = routes.iter()
+        .map(|route| [
+            route.method.as_str().to_string(),
+            route.host.clone().unwrap_or_else(|| "*".to_string()),
+            match &route.query {
+                Some(query) => format!("{}?{query}", route.path),
+                None => route.path.clone(),
+            },
+            route.name.clone().unwrap_or_else(|| "-".to_string()),
+            route.handler.clone().unwrap_or_else(|| "-".to_string()),
+            if route.tags.is_empty() { "-".to_string() } else { route.tags.join(", ") },
+            if route.middleware.is_empty() { "-".to_string() } else { route.middleware.join(", ") },
+            format!("{}:{}", route.location.file(), route.location.line()),
+        ])
+        .collect();
+
+    let mut widths = header.clone().map(|column| column.len());
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let mut table = String::new();
+    for row in std::iter::once(&header).chain(&rows) {
+        let cells: Vec<String> = row.iter().zip(widths)
+            .map(|(cell, width)| format!("{cell:<width$}"))
+            .collect();
+        table.push_str(cells.join("  ").trim_end());
+        table.push('\n');
+    }
+    table
+}
+
+/// One handler for a method and path, selected by its query constraint.
+#[derive(Clone)]
+pub struct RouteCandidate {
+    pub query: Option<QueryConstraint>,
+    pub host: Option<HostPattern>,
+    pub guards: Vec<Arc<dyn Guard>>,
+    pub handler: Arc<Handler>,
+    /// Path parameters, in the order they appear in the path.
+    pub params: Vec<PathParam>,
+    pub priority: i32,
+    /// Where the route was registered, ordering otherwise equal candidates.
+    pub location: &'static Location<'static>,
+}
+
+impl RouteCandidate {
+    /// Checks the host and query constraints, returning the captured host parameters.
+    pub fn matches(&self, host: Option<&str>, query_params: &QueryParams) -> Option<Vec<(String, String)>> {
+        let host_params = match &self.host {
+            Some(pattern) => pattern.matches(host?)?,
+            None => Vec::new(),
+        };
+        self.query.as_ref()
+            .is_none_or(|query| query.matches(query_params))
+            .then_some(host_params)
+    }
+
+    /// Whether the route accepts requests sent to `host`.
+    pub fn serves_host(&self, host: Option<&str>) -> bool {
+        match &self.host {
+            Some(pattern) => host.is_some_and(|host| pattern.matches(host).is_some()),
+            None => true,
+        }
+    }
+
+    /// Runs the guards, returning the rejection status of the first one that fails.
+    pub fn check_guards(&self, req: &Request) -> Result<(), Option<hyper::StatusCode>> {
+        match self.guards.iter().find(|guard| !guard.check(req)) {
+            Some(guard) => Err(guard.rejection()),
+            None => Ok(()),
+        }
+    }
+
+    /// Names the positional parameters captured by the router after this
+    /// route's own path, percent-decoding their values. Returns `None` when a
+    /// value does not satisfy its parameter's constraint.
+    pub fn path_params(&self, params: &matchit::Params) -> Option<PathParams> {
+        self.params.iter()
+            .zip(params.iter())
+            .map(|(param, (_, value))| {
+                let value = percent_decode_str(value).decode_utf8_lossy().into_owned();
+                param.constraint.as_ref()
+                    .is_none_or(|constraint| constraint.matches(&value))
+                    .then(|| (param.name.clone(), value))
+            })
+            .collect()
+    }
+
+    /// The host, path and query constraints of this candidate, for error messages.
+    fn constraints(&self) -> String {
+        let mut constraints: Vec<String> = Vec::new();
+        if let Some(host) = &self.host {
+            constraints.push(format!("host `{host}`"));
+        }
+        for param in &self.params {
+            if let Some(constraint) = &param.constraint {
+                constraints.push(format!("`{{{}:{constraint}}}`", param.name));
+            }
+        }
+        if let Some(query) = &self.query {
+            constraints.push(format!("query `{query}`"));
+        }
+        if constraints.is_empty() {
+            "(no constraints)".to_string()
+        } else {
+            constraints.join(", ")
+        }
+    }
+
+    /// Candidates with a higher priority are tried first, then host-restricted
+    /// ones, then ones with more constrained path parameters (typed before
+    /// regex), then guarded ones, then by query constraint.
+    fn specificity(&self) -> (i32, Option<usize>, usize, usize, usize, (usize, usize)) {
+        (
+            self.priority,
+            self.host.as_ref().map(HostPattern::specificity),
+            self.params.iter().filter(|param| param.constraint.is_some()).count(),
+            self.params.iter().filter(|param| matches!(param.constraint, Some(ParamConstraint::Type(_)))).count(),
+            self.guards.len(),
+            self.query.as_ref().map_or((0, 0), QueryConstraint::specificity),
+        )
+    }
+}
+
+/// Every handler registered for one path, grouped by method.
+#[derive(Clone, Default)]
+pub struct PathRoutes {
+    methods: Vec<(HttpMethod, Vec<RouteCandidate>)>,
+}
+
+impl PathRoutes {
+    /// Candidates for `method` that serve `host`. HEAD falls back to the GET
+    /// candidates unless a HEAD route serves the host explicitly.
+    pub fn candidates(&self, method: &HttpMethod, host: Option<&str>) -> Option<Vec<&RouteCandidate>> {
+        self.registered(method, host).or_else(|| match method {
+            HttpMethod::Head => self.registered(&HttpMethod::Get, host),
+            _ => None,
+        })
+    }
+
+    /// Whether HEAD requests to `host` are answered by the GET handler with the body stripped.
+    pub fn derives_head(&self, host: Option<&str>) -> bool {
+        self.registered(&HttpMethod::Head, host).is_none() && self.registered(&HttpMethod::Get, host).is_some()
+    }
+
+    /// Value of the `Allow` header for this path on `host`; `OPTIONS` is always
+    /// answered. `None` when no route on this path serves the host.
+    pub fn allow(&self, host: Option<&str>) -> Option<String> {
+        let mut methods: Vec<&str> = self.methods.iter()
+            .filter(|(method, _)| self.registered(method, host).is_some())
+            .map(|(method, _)| method.as_str())
+            .collect();
+        if methods.is_empty() {
+            return None;
+        }
+        if self.derives_head(host) {
+            methods.push("HEAD");
+        }
+        if !methods.contains(&"OPTIONS") {
+            methods.push("OPTIONS");
+        }
+        Some(methods.join(", "))
+    }
+
+    fn registered(&self, method: &HttpMethod, host: Option<&str>) -> Option<Vec<&RouteCandidate>> {
+        let candidates: Vec<&RouteCandidate> = self.methods.iter()
+            .find(|(registered, _)| registered == method)
+            .into_iter()
+            .flat_map(|(_, candidates)| candidates)
+            .filter(|candidate| candidate.serves_host(host))
+            .collect();
+        (!candidates.is_empty()).then_some(candidates)
+    }
+}
+
+pub type PathRouter = matchit::Router<PathRoutes>;
+
+#[derive(Debug)]
+pub enum RouteError {
+    Insert {
+        path: String,
+        source: matchit::InsertError,
+    },
+    Ambiguous {
+        method: HttpMethod,
+        path: String,
+        first: String,
+        second: String,
+    },
+    DuplicateName {
+        name: String,
+        first: String,
+        second: String,
+    },
+    ParamConflict {
+        path: String,
+        name: String,
+    },
+    InvalidPath {
+        path: String,
+        message: String,
+    },
+}
+
+impl fmt::Display for RouteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RouteError::Insert { path, source } => write!(f, "Failed to insert route {path}: {source}"),
+            RouteError::Ambiguous { method, path, first, second } => write!(
+                f,
+                "Ambiguous routes for {} {path}: {first} and {second} can match the same request",
+                method.as_str(),
+            ),
+            RouteError::DuplicateName { name, first, second } => write!(
+                f,
+                "Route name `{name}` is used for both {first} and {second}",
+            ),
+            RouteError::InvalidPath { message, .. } => write!(f, "Invalid route path: {message}"),
+            RouteError::ParamConflict { path, name } => write!(
+                f,
+                "Route {path} captures `{name}` from both its host and its path",
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RouteError {}
+
+/// Wraps `handler` so it sees `state` instead of the state of the root app.
+fn scoped_state(handler: Arc<Handler>, state: Arc<StateMap>) -> Arc<Handler> {
+    Arc::new(Box::new(move |mut req| {
+        req.state = state.clone();
+        handler(req)
+    }))
+}
+
+/// Compiles registered routes into a single matchit router keyed by path.
+///
+/// Each path knows every method registered on it, so the service can tell an
+/// unknown path (404) from an unsupported method (405). Candidates sharing a
+/// method and path are ordered by priority and then from the most to the least
+/// specific; candidates with equal specificity that could match the same
+/// request are rejected as ambiguous. Guarded candidates and ones whose path
+/// constraints differ, like `{id:u32}` and `{slug:[a-z-]+}`, are never
+/// ambiguous; with equal specificity they are tried in source order.
+///
+/// Routes from nested apps see their own state layered over `state`.
+pub fn build_routes(routes: &[Route], state: &Arc<StateMap>) -> Result<PathRouter, RouteError> {
+    let mut grouped: Vec<(String, &str, PathRoutes)> = Vec::new();
+
+    for route in routes {
+        let pattern = PathPattern::parse(&route.path)
+            .map_err(|message| RouteError::InvalidPath { path: route.path.clone(), message })?;
+        let positional = pattern.positional();
+        let params: Vec<PathParam> = pattern.params().cloned().collect();
+        let mut handler = apply_middleware(route.success_handler(), &route.middleware);
+        if let Some(own) = &route.state {
+            let mut layered = StateMap::clone(state);
+            layered.extend(own);
+            handler = scoped_state(handler, Arc::new(layered));
+        }
+        if let Some(name) = route.host.iter().flat_map(HostPattern::param_names).find(|name| params.iter().any(|param| param.name == *name)) {
+            return Err(RouteError::ParamConflict { path: route.path.clone(), name: name.to_string() });
+        }
+        let candidate = RouteCandidate {
+            query: route.query.clone(),
+            host: route.host.clone(),
+            guards: route.guards.clone(),
+            handler,
+            params,
+            priority: route.priority,
+            location: route.location,
+        };
+
+        let index = match grouped.iter().position(|(key, _, _)| *key == positional) {
+            Some(index) => index,
+            None => {
+                grouped.push((positional, &route.path, PathRoutes::default()));
+                grouped.len() - 1
+            }
+        };
+        let methods = &mut grouped[index].2.methods;
+        match methods.iter_mut().find(|(method, _)| *method == route.method) {
+            Some((_, candidates)) => candidates.push(candidate),
+            None => methods.push((route.method.clone(), vec![candidate])),
+        }
+    }
+
+    let mut router = PathRouter::new();
+
+    for (positional, path, mut path_routes) in grouped {
+        for (method, candidates) in &mut path_routes.methods {
+            for (i, first) in candidates.iter().enumerate() {
+                for second in &candidates[i + 1..] {
+                    // Guards are opaque, so guarded candidates are never reported as ambiguous
+                    let guarded = !first.guards.is_empty() || !second.guards.is_empty();
+                    // Differently constrained candidates are ordered, not rejected
+                    let constrained = first.params.iter().map(|param| &param.constraint)
+                        .ne(second.params.iter().map(|param| &param.constraint));
+                    let hosts_disjoint = match (&first.host, &second.host) {
+                        (Some(a), Some(b)) => a.is_disjoint(b),
+                        _ => false,
+                    };
+                    let disjoint = guarded || constrained || hosts_disjoint || match (&first.query, &second.query) {
+                        (Some(a), Some(b)) => a.is_disjoint(b),
+                        _ => false,
+                    };
+                    if !disjoint && first.specificity() == second.specificity() {
+                        return Err(RouteError::Ambiguous {
+                            method: method.clone(),
+                            path: path.to_string(),
+                            first: first.constraints(),
+                            second: second.constraints(),
+                        });
+                    }
+                }
+            }
+            // Equally specific candidates keep a fixed order, whatever order they were registered in
+            candidates.sort_by_key(|candidate| {
+                let location = candidate.location;
+                (std::cmp::Reverse(candidate.specificity()), location.file(), location.line(), location.column())
+            });
+        }
+
+        router
+            .insert(positional, path_routes)
+            .map_err(|source| RouteError::Insert { path: path.to_string(), source })?;
+    }
+
+    Ok(router)
+}
@@ -0,0 +1,175 @@
+use std::{
+    future::Future,
+    sync::Arc,
+};
+
+use crate::core::error::MadenError;
+use crate::core::extractor::{FromRequest, FromRequestParts};
+use crate::core::http::{HttpMethod, IntoResponse, Request, Response};
+use crate::core::service::Handler;
+
+/// An async function or closure usable as a route handler.
+///
+/// Implemented for functions taking up to eight extractors: every argument
+/// but the last is extracted with [`FromRequestParts`], the last one with
+/// [`FromRequest`] so it may consume the body. `Args` only tells the impls
+/// apart and is inferred.
+pub trait HandlerFn<Args>: Send + Sync + Sized + 'static {
+    fn into_handler(self) -> Handler;
+}
+
+impl<F, Fut, R> HandlerFn<()> for F
+where
+    F: Fn() -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = R> + Send + 'static,
+    R: IntoResponse,
+{
+    fn into_handler(self) -> Handler {
+        let func = Arc::new(self);
+        Box::new(move |_req| {
+            let func = func.clone();
+            Box::pin(async move { func().await.into_response() })
+        })
+    }
+}
+
+macro_rules! impl_handler_fn {
+    ($($part:ident),* ; $last:ident) => {
+        impl<F, Fut, R, M, $($part,)* $last> HandlerFn<(M, $($part,)* $last)> for F
+        where
+            F: Fn($($part,)* $last) -> Fut + Send + Sync + 'static,
+            Fut: Future<Output = R> + Send + 'static,
+            R: IntoResponse,
+            $($part: FromRequestParts + Send + 'static,)*
+            $last: FromRequest<M> + Send + 'static,
+        {
+            #[allow(non_snake_case)]
+            fn into_handler(self) -> Handler {
+                let func = Arc::new(self);
+                Box::new(move |req: Request| {
+                    let func = func.clone();
+                    Box::pin(async move {
+                        let result: Result<Response, MadenError> = async {
+                            $(let $part = <$part as FromRequestParts>::from_request_parts(&req).await?;)*
+                            let $last = <$last as FromRequest<M>>::from_request(req).await?;
+                            Ok(func($($part,)* $last).await.into_response())
+                        }.await;
+
+                        match result {
+                            Ok(response) => response,
+                            Err(error) => error.into_response(),
+                        }
+                    })
+                })
+            }
+        }
+    };
+}
+
+impl_handler_fn!(; T1);
+impl_handler_fn!(T1; T2);
+impl_handler_fn!(T1, T2; T3);
+impl_handler_fn!(T1, T2, T3; T4);
+impl_handler_fn!(T1, T2, T3, T4; T5);
+impl_handler_fn!(T1, T2, T3, T4, T5; T6);
+impl_handler_fn!(T1, T2, T3, T4, T5, T6; T7);
+impl_handler_fn!(T1, T2, T3, T4, T5, T6, T7; T8);
+
+/// Handlers for one path, keyed by method, built with [`get`], [`post`], ...
+///
+/// ```ignore
+/// Maden::new().route("/users", get(list_users).post(create_user));
+/// ```
+#[derive(Default)]
+pub struct MethodRouter {
+    pub(crate) handlers: Vec<(HttpMethod, &'static str, Handler)>,
+    pub(crate) name: Option<String>,
+    pub(crate) host: Option<String>,
+}
+
+impl MethodRouter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a handler for `method`.
+    pub fn on<H, T>(mut self, method: HttpMethod, handler: H) -> Self
+    where
+        H: HandlerFn<T>,
+    {
+        self.handlers.push((method, std::any::type_name::<H>(), handler.into_handler()));
+        self
+    }
+
+    pub fn get<H: HandlerFn<T>, T>(self, handler: H) -> Self {
+        self.on(HttpMethod::Get, handler)
+    }
+
+    pub fn post<H: HandlerFn<T>, T>(self, handler: H) -> Self {
+        self.on(HttpMethod::Post, handler)
+    }
+
+    pub fn put<H: HandlerFn<T>, T>(self, handler: H) -> Self {
+        self.on(HttpMethod::Put, handler)
+    }
+
+    pub fn delete<H: HandlerFn<T>, T>(self, handler: H) -> Self {
+        self.on(HttpMethod::Delete, handler)
+    }
+
+    pub fn patch<H: HandlerFn<T>, T>(self, handler: H) -> Self {
+        self.on(HttpMethod::Patch, handler)
+    }
+
+    pub fn head<H: HandlerFn<T>, T>(self, handler: H) -> Self {
+        self.on(HttpMethod::Head, handler)
+    }
+
+    pub fn options<H: HandlerFn<T>, T>(self, handler: H) -> Self {
+        self.on(HttpMethod::Options, handler)
+    }
+
+    /// Restricts the routes of this path to a host, e.g. `{tenant}.example.com`.
+    pub fn host(mut self, pattern: impl Into<String>) -> Self {
+        self.host = Some(pattern.into());
+        self
+    }
+
+    /// Names the routes of this path for `url_for`.
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+}
+
+pub fn on<H: HandlerFn<T>, T>(method: HttpMethod, handler: H) -> MethodRouter {
+    MethodRouter::new().on(method, handler)
+}
+
+pub fn get<H: HandlerFn<T>, T>(handler: H) -> MethodRouter {
+    MethodRouter::new().get(handler)
+}
+
+pub fn post<H: HandlerFn<T>, T>(handler: H) -> MethodRouter {
+    MethodRouter::new().post(handler)
+}
+
+pub fn put<H: HandlerFn<T>, T>(handler: H) -> MethodRouter {
+    MethodRouter::new().put(handler)
+}
+
+pub fn delete<H: HandlerFn<T>, T>(handler: H) -> MethodRouter {
+    MethodRouter::new().delete(handler)
+}
+
+pub fn patch<H: HandlerFn<T>, T>(handler: H) -> MethodRouter {
+    MethodRouter::new().patch(handler)
+}
+
+pub fn head<H: HandlerFn<T>, T>(handler: H) -> MethodRouter {
+    MethodRouter::new().head(handler)
+}
+
+pub fn options<H: HandlerFn<T>, T>(handler: H) -> MethodRouter {
+    MethodRouter::new().options(handler)
+}
@@ -1,51 +1,313 @@
 use std::{
+    any::{Any, TypeId},
     collections::HashMap,
     net::SocketAddr,
     sync::Arc,
 };
 
+use cookie::Key;
 use hyper_util::rt::{TokioExecutor, TokioIo};
 use maden_config::Config;
+use maden_pattern::join_path;
 use tokio::net::TcpListener;
 use tokio_rustls::TlsAcceptor;
 use rustls::ServerConfig as RustlsServerConfig;
 
 use crate::core::http::HttpMethod;
+use crate::core::middleware::Middleware;
+use crate::core::router::{build_routes, HostPattern, route_table, PathRouter, QueryConstraint, Route, RouteInfo};
+use crate::core::routing::{HandlerFn, MethodRouter};
 use crate::core::service::{Handler, MadenService};
+use crate::core::state::StateMap;
+use crate::core::url::RouteUrls;
+use crate::core::error::MadenError;
 use crate::core::tls::{load_certs, load_private_key};
 
-pub type MadenRoutes = Arc<HashMap<HttpMethod, matchit::Router<Arc<Handler>>>>;
+pub type MadenRoutes = Arc<PathRouter>;
+
+type HandlerConstructor = Box<dyn FnOnce(&StateMap) -> Box<dyn Any + Send + Sync> + Send>;
+
+/// Example `[cookie] secret` shown in maden.toml, refused so it never signs real cookies.
+const PLACEHOLDER_COOKIE_SECRET: &str = "change-me-to-a-long-random-secret-of-32-bytes-or-more";
 
 pub struct Maden {
-    pub routes: HashMap<HttpMethod, matchit::Router<Arc<Handler>>>,
+    pub routes: Vec<Route>,
+    pub state: StateMap,
+    middleware: Vec<Middleware>,
+    host: Option<HostPattern>,
+    fallback: Option<Arc<Handler>>,
+    handlers: StateMap,
+    handler_constructors: HashMap<TypeId, HandlerConstructor>,
+    setup_errors: Vec<String>,
 }
 
 impl Maden {
     pub fn new() -> Self {
-        let mut routes = HashMap::new();
-        routes.insert(HttpMethod::Get, matchit::Router::new());
-        routes.insert(HttpMethod::Post, matchit::Router::new());
-        routes.insert(HttpMethod::Put, matchit::Router::new());
-        routes.insert(HttpMethod::Delete, matchit::Router::new());
-        routes.insert(HttpMethod::Patch, matchit::Router::new());
-        routes.insert(HttpMethod::Options, matchit::Router::new());
-        routes.insert(HttpMethod::Head, matchit::Router::new());
-
         Self {
-            routes
+            routes: Vec::new(),
+            state: StateMap::new(),
+            middleware: Vec::new(),
+            host: None,
+            fallback: None,
+            handlers: StateMap::new(),
+            handler_constructors: HashMap::new(),
+            setup_errors: Vec::new(),
+        }
+    }
+
+    /// Registers the instance shared by the `&self` methods of a `#[handler]` struct.
+    pub fn provide_handler<H: Send + Sync + 'static>(&mut self, handler: H) -> &mut Self {
+        self.handlers.insert(handler);
+        self
+    }
+
+    /// Registers a constructor for a `#[handler]` struct, called once with the
+    /// managed state when its routes are added.
+    pub fn handler_constructor<H, F>(&mut self, constructor: F) -> &mut Self
+    where
+        H: Send + Sync + 'static,
+        F: FnOnce(&StateMap) -> H + Send + 'static,
+    {
+        self.handler_constructors.insert(
+            TypeId::of::<H>(),
+            Box::new(move |state| Box::new(constructor(state)) as Box<dyn Any + Send + Sync>),
+        );
+        self
+    }
+
+    /// Returns the registered instance of a `#[handler]` struct, building it
+    /// from its constructor on first use.
+    pub fn handler_instance<H: Send + Sync + 'static>(&mut self) -> Option<Arc<H>> {
+        if let Some(handler) = self.handlers.get::<H>() {
+            return Some(handler);
         }
+
+        let constructor = self.handler_constructors.remove(&TypeId::of::<H>())?;
+        let handler = constructor(&self.state).downcast::<H>().ok()?;
+        self.handlers.insert(*handler);
+        self.handlers.get::<H>()
+    }
+
+    /// Records a problem found while registering routes; [`Maden::run`]
+    /// reports it and does not start the server.
+    pub fn setup_error(&mut self, message: impl Into<String>) -> &mut Self {
+        self.setup_errors.push(message.into());
+        self
+    }
+
+    /// Adds a shared value that handlers can reach through `State<T>`.
+    pub fn with_state<T: Send + Sync + 'static>(mut self, value: T) -> Self {
+        self.state.insert(value);
+        self
+    }
+
+    /// Same as [`Maden::with_state`], for use through `&mut Maden`.
+    pub fn manage<T: Send + Sync + 'static>(&mut self, value: T) -> &mut Self {
+        self.state.insert(value);
+        self
+    }
+
+    /// Handles requests that match no route, instead of the default 404 response.
+    pub fn fallback<H, T>(&mut self, handler: H) -> &mut Self
+    where
+        H: HandlerFn<T>,
+    {
+        self.fallback = Some(Arc::new(handler.into_handler()));
+        self
+    }
+
+    /// Registers the handlers of `methods` on `path`.
+    ///
+    /// ```ignore
+    /// let maden = Maden::new().route("/x", get(my_fn).post(other));
+    /// ```
+    #[track_caller]
+    pub fn route(mut self, path: &str, methods: MethodRouter) -> Self {
+        self.add_routes(path, methods);
+        self
     }
 
-    pub fn add_route(&mut self, method: HttpMethod, path: &str, _query_string: Option<String>, handler: Handler) {
-        if let Some(router) = self.routes.get_mut(&method) {
-            if let Err(e) = router.insert(path, Arc::new(handler)) {
-                maden_log::error!("Failed to insert route {path}: {e}");
+    /// Same as [`Maden::route`], for use through `&mut Maden`.
+    #[track_caller]
+    pub fn add_routes(&mut self, path: &str, methods: MethodRouter) -> &mut Self {
+        for (method, handler_name, handler) in methods.handlers {
+            let route = self.add_route(method, path, None, handler);
+            route.handler_name(handler_name);
+            if let Some(name) = &methods.name {
+                route.name(name.clone());
             }
+            if let Some(host) = &methods.host {
+                route.host(host);
+            }
+        }
+        self
+    }
+
+    #[track_caller]
+    pub fn add_route(&mut self, method: HttpMethod, path: &str, query_string: Option<&str>, handler: Handler) -> &mut Route {
+        self.routes.push(Route::new(method, path, query_string.map(QueryConstraint::parse), handler));
+        self.routes.last_mut().expect("route was just added")
+    }
+
+    /// Adds middleware around every route of this app, outside route-level middleware.
+    ///
+    /// Routes of an app mounted with [`Maden::nest`] or [`Maden::merge`] keep the
+    /// middleware of their own app, inside that of the app they are mounted on.
+    pub fn middleware(&mut self, middleware: Middleware) -> &mut Self {
+        self.middleware.push(middleware);
+        self
+    }
+
+    /// Restricts the routes of this app that have no host of their own to
+    /// `pattern`, e.g. to serve one API domain per app and [`Maden::merge`] them.
+    pub fn host(&mut self, pattern: &str) -> &mut Self {
+        self.host = Some(HostPattern::parse(pattern));
+        self
+    }
+
+    /// Mounts the routes of `other` under `prefix`.
+    ///
+    /// The nested routes keep `other`'s middleware and see `other`'s state
+    /// layered over the state of this app. Its fallback is not used.
+    pub fn nest(&mut self, prefix: &str, other: Maden) -> &mut Self {
+        self.mount(prefix, other)
+    }
+
+    /// Adds the routes of `other` as if they were registered on this app,
+    /// keeping `other`'s middleware and state like [`Maden::nest`].
+    ///
+    /// `other`'s fallback is used if this app has none.
+    pub fn merge(&mut self, mut other: Maden) -> &mut Self {
+        if self.fallback.is_none() {
+            self.fallback = other.fallback.take();
         }
+        self.mount("", other)
     }
 
-    pub async fn run(self, config: Config) {
-        let routes = Arc::new(self.routes);
+    fn mount(&mut self, prefix: &str, mut other: Maden) -> &mut Self {
+        self.setup_errors.append(&mut other.setup_errors);
+        let own_state = std::mem::take(&mut other.state);
+        for mut route in other.take_routes() {
+            route.path = join_path(prefix, &route.path);
+            if !own_state.is_empty() || route.state.is_some() {
+                let mut state = own_state.clone();
+                if let Some(inner) = &route.state {
+                    state.extend(inner);
+                }
+                route.state = Some(state);
+            }
+            self.routes.push(route);
+        }
+        self
+    }
+
+    /// Removes the routes with this app's middleware and host folded into their own.
+    fn take_routes(&mut self) -> Vec<Route> {
+        let routes = std::mem::take(&mut self.routes);
+        self.fold_into(routes)
+    }
+
+    /// Folds this app's middleware and host into those of `routes`.
+    fn fold_into(&self, mut routes: Vec<Route>) -> Vec<Route> {
+        for route in &mut routes {
+            route.middleware.splice(0..0, self.middleware.iter().cloned());
+            if route.host.is_none() {
+                route.host = self.host.clone();
+            }
+        }
+        routes
+    }
+
+    /// The registered routes, sorted by path and then method so listings are stable.
+    pub fn routes(&self) -> Vec<RouteInfo> {
+        let mut routes: Vec<RouteInfo> = self.routes.iter()
+            .map(|route| {
+                let mut info = route.info();
+                info.middleware.splice(0..0, self.middleware.iter().map(|middleware| middleware.name().to_string()));
+                if info.host.is_none() {
+                    info.host = self.host.as_ref().map(ToString::to_string);
+                }
+                info
+            })
+            .collect();
+        routes.sort_by(|a, b| (&a.path, a.method.as_str(), &a.query).cmp(&(&b.path, b.method.as_str(), &b.query)));
+        routes
+    }
+
+    /// [`Maden::routes`] formatted as a plain-text table.
+    pub fn route_table(&self) -> String {
+        route_table(&self.routes())
+    }
+
+    /// Builds the URL of a named route from the routes registered so far.
+    ///
+    /// See [`RouteUrls::url_for`].
+    pub fn url_for<I, K, V>(&self, name: &str, params: I) -> Result<String, MadenError>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: std::fmt::Display,
+    {
+        RouteUrls::from_routes(&self.routes)
+            .map_err(|e| MadenError::internal_server_error(e.to_string()))?
+            .url_for(name, params)
+    }
+
+    /// Checks the routes the way [`Maden::run`] does before starting: problems
+    /// recorded with [`Maden::setup_error`], route names used twice and
+    /// ambiguous or invalid routes. Returns every problem found.
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        let mut errors = self.setup_errors.clone();
+        if let Err(e) = RouteUrls::from_routes(&self.routes) {
+            errors.push(e.to_string());
+        }
+        let routes = self.fold_into(self.routes.clone());
+        if let Err(e) = build_routes(&routes, &Arc::new(self.state.clone())) {
+            errors.push(e.to_string());
+        }
+
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+
+    pub async fn run(mut self, config: Config) {
+        if let Err(errors) = self.validate() {
+            for error in &errors {
+                maden_log::error!("{error}");
+            }
+            return;
+        }
+        match RouteUrls::from_routes(&self.routes) {
+            Ok(urls) => self.state.insert(urls),
+            Err(e) => {
+                maden_log::error!("{e}");
+                return;
+            }
+        }
+        // Signed and private cookies use a key derived from the configured secret,
+        // unless one was managed explicitly
+        if let Some(cookie) = &config.cookie
+            && !self.state.contains::<Key>()
+        {
+            if cookie.secret.len() < 32 {
+                maden_log::error!("The cookie secret must be at least 32 bytes long");
+                return;
+            }
+            if cookie.secret == PLACEHOLDER_COOKIE_SECRET {
+                maden_log::error!("The cookie secret is the example value from maden.toml; replace it with a random secret");
+                return;
+            }
+            self.state.insert(Key::derive_from(cookie.secret.as_bytes()));
+        }
+        let fallback = self.fallback.take();
+        let route_list = self.take_routes();
+        let state = Arc::new(self.state);
+        let routes = match build_routes(&route_list, &state) {
+            Ok(routes) => Arc::new(routes),
+            Err(e) => {
+                maden_log::error!("{e}");
+                return;
+            }
+        };
         let addr = SocketAddr::from(([0, 0, 0, 0], config.server.port));
         let listener = match TcpListener::bind(addr).await {
             Ok(l) => l,
@@ -107,6 +369,8 @@ impl Maden {
                 // HTTPS connection
                 let service = MadenService {
                     routes: routes.clone(),
+                    state: state.clone(),
+                    fallback: fallback.clone(),
                 };
                 let acceptor = acceptor.clone();
 
@@ -127,6 +391,8 @@ impl Maden {
                 let io = TokioIo::new(stream);
                 let service = MadenService {
                     routes: routes.clone(),
+                    state: state.clone(),
+                    fallback: fallback.clone(),
                 };
 
                 tokio::task::spawn(async move {
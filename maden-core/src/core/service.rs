@@ -3,16 +3,18 @@ use std::{
     convert::Infallible,
     future::Future,
     pin::Pin,
+    sync::Arc,
 };
 
-use http_body_util::{BodyExt, Full};
+use http_body_util::Full;
 use hyper::{
     body::{Bytes, Incoming},
     service::Service,
     Request as HyperRequest, Response as HyperResponse,
 };
 
-use crate::core::http::{HttpMethod, Request, Response};
+use crate::core::http::{HttpMethod, PathParams, QueryParams, Request, Response, UnreadBody};
+use crate::core::state::StateMap;
 use crate::MadenRoutes;
 use crate::MadenError;
 use crate::IntoResponse;
@@ -22,6 +24,8 @@ pub type Handler = Box<dyn Fn(Request) -> Pin<Box<dyn Future<Output = Response>
 #[derive(Clone)]
 pub struct MadenService {
     pub routes: MadenRoutes,
+    pub state: Arc<StateMap>,
+    pub fallback: Option<Arc<Handler>>,
 }
 
 impl Service<HyperRequest<Incoming>> for MadenService {
@@ -30,59 +34,140 @@ impl Service<HyperRequest<Incoming>> for MadenService {
     type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
 
     fn call(&self, hyper_req: HyperRequest<Incoming>) -> Self::Future {
-        let method = HttpMethod::from_hyper(hyper_req.method()).unwrap();
+        let method = HttpMethod::from_hyper(hyper_req.method());
         let path = hyper_req.uri().path().to_string();
 
         maden_log::info!("Incoming request: {method:?} {path}");
 
-        let (matched_handler, extracted_params) = self.routes.get(&method)
-            .and_then(|router| router.at(&path).ok())
-            .map(|m| (Some(m.value.clone()), m.params.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()))
-            .unwrap_or((None, HashMap::new()));
+        let query_params = hyper_req.uri().query().map_or_else(QueryParams::new, QueryParams::parse);
 
-        Box::pin(async move {
-            let (parts, body) = hyper_req.into_parts();
-            let body_bytes = body.collect().await.unwrap().to_bytes();
+        let host = request_host(&hyper_req);
 
-            let mut headers = HashMap::new();
-            for (name, value) in parts.headers.iter() {
-                headers.insert(name.to_string(), value.to_str().unwrap_or("").to_string());
+        // Repeated headers are joined into one value, `Cookie` headers with `; `
+        let mut headers: HashMap<String, String> = HashMap::new();
+        for (name, value) in hyper_req.headers().iter() {
+            let value = value.to_str().unwrap_or("");
+            match headers.get_mut(name.as_str()) {
+                Some(existing) => {
+                    existing.push_str(if name == hyper::header::COOKIE { "; " } else { ", " });
+                    existing.push_str(value);
+                }
+                None => {
+                    headers.insert(name.to_string(), value.to_string());
+                }
             }
+        }
 
-            let query_params = parts.uri.query().map_or_else(HashMap::new, |query| {
-                query.split('&').filter_map(|pair| {
-                    let mut parts = pair.splitn(2, '=');
-                    Some((parts.next()?.to_string(), parts.next()?.to_string()))
-                }).collect()
-            });
-
-            let maden_req = Request::new(
-                method,
-                path,
-                headers,
-                extracted_params,
-                query_params,
-                body_bytes.to_vec(),
-            );
+        // Guards only see the head; the body is read by the extractor that needs it
+        let mut maden_req = Request::new(
+            method.clone(),
+            path.clone(),
+            headers,
+            PathParams::new(),
+            query_params,
+            Vec::new(),
+        ).with_state(self.state.clone());
+
+        // `allow` is set when the path exists on this host but has no handler for this method,
+        // `rejection` when a guard that failed asks for a specific status
+        let mut allow = None;
+        let mut options_allow = None;
+        let mut rejection = None;
+        let mut strip_body = false;
+        let mut matched_handler = None;
+        if let Ok(m) = self.routes.at(&path) {
+            // Routes restricted to other hosts are treated as absent
+            match m.value.candidates(&method, host.as_deref()) {
+                Some(candidates) => {
+                    // HEAD served by a GET handler drops the body but keeps its length
+                    strip_body = method == HttpMethod::Head && m.value.derives_head(host.as_deref());
+                    for candidate in candidates {
+                        let Some(host_params) = candidate.matches(host.as_deref(), &maden_req.query_params) else {
+                            continue;
+                        };
+                        let Some(path_params) = candidate.path_params(&m.params) else {
+                            continue;
+                        };
+                        if let Err(status) = candidate.check_guards(&maden_req) {
+                            rejection = rejection.or(status);
+                            continue;
+                        }
+                        maden_req.path_params = path_params;
+                        maden_req.host_params = host_params.into_iter().collect();
+                        matched_handler = Some(candidate.handler.clone());
+                        if method == HttpMethod::Options {
+                            options_allow = m.value.allow(host.as_deref());
+                            maden_req.allow = options_allow.clone();
+                        }
+                        break;
+                    }
+                }
+                None => allow = m.value.allow(host.as_deref()),
+            }
+        }
+
+        maden_req.unread_body = UnreadBody::new(hyper_req.into_body());
+        let fallback = self.fallback.clone();
 
-            maden_log::debug!("Request details: {{ method: {:?}, path: {:?}, headers: {:?}, path_params: {:?}, query_params: {:?}, body_len: {} }}",
+        Box::pin(async move {
+            maden_log::debug!("Request details: {{ method: {:?}, path: {:?}, headers: {:?}, path_params: {:?}, host_params: {:?}, query_params: {:?} }}",
                 maden_req.method,
                 maden_req.path,
                 maden_req.headers,
                 maden_req.path_params,
+                maden_req.host_params,
                 maden_req.query_params,
-                maden_req.body.len()
             );
 
-            let maden_res = match matched_handler {
-                Some(handler) => {
-                    handler(maden_req).await
+            let maden_res = match (matched_handler, allow, rejection) {
+                (Some(handler), _, _) if strip_body => {
+                    let mut response = handler(maden_req).await;
+                    if !response.headers.keys().any(|key| key.eq_ignore_ascii_case("Content-Length")) {
+                        response.headers.insert("Content-Length".to_string(), response.body.len().to_string());
+                    }
+                    response.body.clear();
+                    response
+                }
+                // Explicit OPTIONS handlers get the path's `Allow` unless they set their own
+                (Some(handler), _, _) if options_allow.is_some() => {
+                    let response = handler(maden_req).await;
+                    match options_allow {
+                        Some(allow) if !response.headers.keys().any(|key| key.eq_ignore_ascii_case("Allow")) => response.with_header("Allow", &allow),
+                        _ => response,
+                    }
+                }
+                (Some(handler), _, _) => handler(maden_req).await,
+                (None, Some(allow), _) if maden_req.method == HttpMethod::Options => {
+                    Response::new(204).with_header("Allow", &allow)
+                }
+                (None, Some(allow), _) => MadenError::method_not_allowed(format!("Method {} is not allowed for this path.", maden_req.method.as_str()))
+                    .into_response()
+                    .with_header("Allow", &allow),
+                (None, None, Some(status)) => {
+                    MadenError::new(status, status.canonical_reason().unwrap_or("Request rejected.")).into_response()
+                }
+                (None, None, None) => match fallback {
+                    Some(handler) => handler(maden_req).await,
+                    None => MadenError::not_found("Route not found.").into_response(),
                 },
-                None => MadenError::not_found("Route not found.").into_response(),
             };
 
             let hyper_res: HyperResponse<Full<Bytes>> = maden_res.into_response().into();
             Ok(hyper_res)
         })
     }
-}
\ No newline at end of file
+}
+
+/// Host the request was sent to, from the URI authority (HTTP/2) or the
+/// `Host` header, without the port.
+fn request_host(req: &HyperRequest<Incoming>) -> Option<String> {
+    let authority = match req.uri().host() {
+        Some(host) => host,
+        None => req.headers().get(hyper::header::HOST)?.to_str().ok()?,
+    };
+    let host = match authority.strip_prefix('[') {
+        Some(ipv6) => ipv6.split(']').next().unwrap_or_default(),
+        None => authority.split(':').next().unwrap_or_default(),
+    };
+    Some(host.to_ascii_lowercase())
+}
@@ -0,0 +1,144 @@
+use std::{
+    collections::HashMap,
+    fmt::Display,
+};
+
+use maden_pattern::{PathPart, PathPattern};
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
+
+use crate::core::error::MadenError;
+use crate::core::router::{QueryConstraint, Route, RouteError};
+
+/// Characters escaped in a path segment (RFC 3986 `pchar`), plus `/` so a
+/// parameter value can never add segments.
+const PATH_SEGMENT: &AsciiSet = &CONTROLS
+    .add(b' ').add(b'"').add(b'#').add(b'%').add(b'/').add(b'<').add(b'>')
+    .add(b'?').add(b'[').add(b'\\').add(b']').add(b'^').add(b'`').add(b'{')
+    .add(b'|').add(b'}');
+
+/// Path patterns and query constraints of named routes, used to build URLs
+/// from route names.
+///
+/// Available to handlers through `State<RouteUrls>` and [`Request::url_for`](crate::Request::url_for).
+#[derive(Clone, Debug, Default)]
+pub struct RouteUrls {
+    patterns: HashMap<String, (String, Option<QueryConstraint>)>,
+}
+
+impl RouteUrls {
+    /// Collects the named routes; one name cannot refer to two different
+    /// paths or query constraints.
+    pub fn from_routes(routes: &[Route]) -> Result<Self, RouteError> {
+        let mut patterns: HashMap<String, (String, Option<QueryConstraint>)> = HashMap::new();
+        for route in routes {
+            let Some(name) = &route.name else {
+                continue;
+            };
+            let target = (route.path.clone(), route.query.clone());
+            match patterns.get(name) {
+                Some(existing) if *existing != target => {
+                    return Err(RouteError::DuplicateName {
+                        name: name.clone(),
+                        first: route_target(existing),
+                        second: route_target(&target),
+                    });
+                }
+                Some(_) => {}
+                None => {
+                    patterns.insert(name.clone(), target);
+                }
+            }
+        }
+        Ok(Self { patterns })
+    }
+
+    /// Builds the URL of the route called `name`.
+    ///
+    /// Each `{param}` is replaced by its percent-encoded value; a catch-all
+    /// `{*param}` keeps its `/` separators. Parameters the path does not use
+    /// are appended as the query string, after the pairs the route's query
+    /// constraint requires. A required key without a fixed value (`query = "page"`)
+    /// must be given in `params`.
+    pub fn url_for<I, K, V>(&self, name: &str, params: I) -> Result<String, MadenError>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: Display,
+    {
+        let (pattern, query) = self.patterns.get(name)
+            .ok_or_else(|| MadenError::internal_server_error(format!("No route named `{name}`")))?;
+        let mut params: Vec<(String, String)> = params.into_iter()
+            .map(|(key, value)| (key.as_ref().to_string(), value.to_string()))
+            .collect();
+
+        let pattern = PathPattern::parse(pattern).map_err(MadenError::internal_server_error)?;
+        let mut url = String::new();
+        for part in &pattern.parts {
+            let param = match part {
+                PathPart::Literal(text) => {
+                    url.push_str(text);
+                    continue;
+                }
+                PathPart::Param(param) => param,
+            };
+
+            let index = params.iter().position(|(key, _)| *key == param.name).ok_or_else(|| {
+                MadenError::internal_server_error(format!("Missing parameter `{}` for route `{name}`", param.name))
+            })?;
+            let (_, value) = params.remove(index);
+            if let Some(constraint) = param.constraint.as_ref().filter(|constraint| !constraint.matches(&value)) {
+                return Err(MadenError::internal_server_error(format!(
+                    "Parameter `{}` of route `{name}` must match `{constraint}`, got `{value}`",
+                    param.name,
+                )));
+            }
+
+            if param.catch_all {
+                let segments: Vec<String> = value.split('/')
+                    .map(|segment| utf8_percent_encode(segment, PATH_SEGMENT).to_string())
+                    .collect();
+                url.push_str(&segments.join("/"));
+            } else {
+                url.extend(utf8_percent_encode(&value, PATH_SEGMENT));
+            }
+        }
+
+        // Pairs the query constraint requires come first, so the URL routes back here
+        let mut query_pairs = Vec::new();
+        for (key, required) in query.iter().flat_map(QueryConstraint::pairs) {
+            let given = params.iter().position(|(param, _)| param == key).map(|index| params.remove(index));
+            match (required, given) {
+                (Some(required), Some((_, value))) if value != *required => {
+                    return Err(MadenError::internal_server_error(format!(
+                        "Query parameter `{key}` of route `{name}` must be `{required}`, got `{value}`",
+                    )));
+                }
+                (Some(required), _) => query_pairs.push((key.clone(), required.clone())),
+                (None, Some(pair)) => query_pairs.push(pair),
+                (None, None) => {
+                    return Err(MadenError::internal_server_error(format!(
+                        "Missing query parameter `{key}` for route `{name}`",
+                    )));
+                }
+            }
+        }
+        query_pairs.append(&mut params);
+
+        if !query_pairs.is_empty() {
+            let query = form_urlencoded::Serializer::new(String::new())
+                .extend_pairs(&query_pairs)
+                .finish();
+            url.push('?');
+            url.push_str(&query);
+        }
+
+        Ok(url)
+    }
+}
+
+fn route_target((path, query): &(String, Option<QueryConstraint>)) -> String {
+    match query {
+        Some(query) => format!("{path}?{query}"),
+        None => path.clone(),
+    }
+}
@@ -1,11 +1,26 @@
 pub mod core;
 
-pub use core::http::{HttpMethod, Request, Response, IntoResponse};
+pub use core::http::{HttpMethod, PathParams, QueryParams, Request, Response, IntoResponse};
+pub use core::response::Html;
+pub use hyper::StatusCode;
 pub use core::error::MadenError;
-pub use core::extractor::{FromRequest, Path, Query, Json, extract_path_param};
+pub use core::extractor::{FromRequest, FromRequestParts, Path, Host, Query, Json, Form, State, extract_path_param};
+pub use core::multipart::{Field, Multipart, MultipartConfig, SpooledFile};
+pub use core::cookies::{CookieJar, PrivateCookieJar, SignedCookieJar};
+pub use cookie::{self, Cookie, Key, SameSite};
+pub use core::state::StateMap;
+pub use core::url::RouteUrls;
+pub use core::router::{HostPattern, RouteInfo};
+pub use maden_pattern::{ParamConstraint, PathPattern};
+pub use core::middleware::{Middleware, Next};
+pub use core::guard::{Consumes, Guard, HeaderGuard, Produces};
+pub use core::routing::{delete, get, head, on, options, patch, post, put, HandlerFn, MethodRouter};
 pub use crate::core::server::Maden;
 pub use maden_macros::handler;
 
+#[doc(hidden)]
+pub use core::private as __private;
+
 pub struct HandlerFactory(pub fn(&mut Maden));
 
 inventory::collect!(HandlerFactory);
@@ -0,0 +1,331 @@
+use maden_pattern::{PathPattern, TYPE_CONSTRAINTS};
+use quote::quote;
+use syn::{LitStr, Type};
+use syn::parse::{Parse, ParseBuffer};
+
+pub(crate) struct HandlerArgs {
+    pub(crate) method: Option<LitStr>,
+    pub(crate) path: LitStr,
+    pub(crate) query: Option<LitStr>,
+    pub(crate) status: Option<u16>,
+    pub(crate) headers: Vec<(LitStr, LitStr)>,
+    pub(crate) content_type: Option<LitStr>,
+    pub(crate) name: Option<LitStr>,
+    pub(crate) host: Option<LitStr>,
+    pub(crate) consumes: Option<LitStr>,
+    pub(crate) produces: Option<LitStr>,
+    pub(crate) header_guards: Vec<(LitStr, LitStr)>,
+    pub(crate) guards: Vec<syn::Expr>,
+    pub(crate) priority: Option<i32>,
+}
+
+pub(crate) const UNKNOWN_ROUTE_ARGUMENT: &str =
+    "unknown route argument; expected `path`, `method`, `query`, `host`, `name`, `status`, `content_type`, `headers(...)`, `consumes`, `produces`, `header(...)`, `guard` or `priority`";
+
+/// One `"Name" = "value"` entry of `headers(...)`.
+pub(crate) struct HeaderArg {
+    pub(crate) name: LitStr,
+    pub(crate) value: LitStr,
+}
+
+impl Parse for HeaderArg {
+    fn parse(input: &ParseBuffer) -> syn::Result<Self> {
+        let name: LitStr = input.parse()?;
+        input.parse::<syn::Token![=]>()?;
+        let value: LitStr = input.parse()?;
+
+        if name.value().is_empty() || !name.value().bytes().all(is_token_char) {
+            return Err(syn::Error::new_spanned(&name, "invalid header name"));
+        }
+        if !value.value().bytes().all(|b| b == b'\t' || (b' '..=b'~').contains(&b)) {
+            return Err(syn::Error::new_spanned(&value, "invalid header value"));
+        }
+
+        Ok(HeaderArg { name, value })
+    }
+}
+
+/// `tchar` from RFC 9110, the characters allowed in header names and media types.
+pub(crate) fn is_token_char(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b)
+}
+
+/// Checks a `type/subtype` media type, parameters allowed.
+pub(crate) fn expect_media_type(value: &LitStr) -> syn::Result<()> {
+    let media_type = value.value();
+    let essence = media_type.split(';').next().unwrap_or_default().trim();
+    let valid = essence.split_once('/').is_some_and(|(kind, subtype)| {
+        !kind.is_empty() && !subtype.is_empty()
+            && kind.bytes().all(is_token_char) && subtype.bytes().all(is_token_char)
+    });
+    if valid {
+        Ok(())
+    } else {
+        Err(syn::Error::new_spanned(value, "expected a media type like `application/json`"))
+    }
+}
+
+pub(crate) fn expect_str(expr: &syn::Expr) -> syn::Result<LitStr> {
+    if let syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(lit_str), .. }) = expr {
+        Ok(lit_str.clone())
+    } else {
+        Err(syn::Error::new_spanned(expr, "expected string literal"))
+    }
+}
+
+impl Parse for HandlerArgs {
+    fn parse(input: &ParseBuffer) -> syn::Result<Self> {
+        let mut method: Option<LitStr> = None;
+        let mut path: Option<LitStr> = None;
+        let mut query: Option<LitStr> = None;
+        let mut status: Option<u16> = None;
+        let mut headers: Vec<(LitStr, LitStr)> = Vec::new();
+        let mut content_type: Option<LitStr> = None;
+        let mut name: Option<LitStr> = None;
+        let mut host: Option<LitStr> = None;
+        let mut consumes: Option<LitStr> = None;
+        let mut produces: Option<LitStr> = None;
+        let mut header_guards: Vec<(LitStr, LitStr)> = Vec::new();
+        let mut guards: Vec<syn::Expr> = Vec::new();
+        let mut priority: Option<i32> = None;
+
+        if input.peek(LitStr) {
+            path = Some(input.parse()?);
+            if !input.is_empty() {
+                input.parse::<syn::Token![,]>()?;
+            }
+        }
+
+        while !input.is_empty() {
+            let meta: syn::Meta = input.parse()?;
+
+            match &meta {
+                syn::Meta::NameValue(nv) if nv.path.is_ident("status") => {
+                    let syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Int(lit_int), .. }) = &nv.value else {
+                        return Err(syn::Error::new_spanned(&nv.value, "expected integer status code"));
+                    };
+                    let code: u16 = lit_int.base10_parse()?;
+                    if !(100..=599).contains(&code) {
+                        return Err(syn::Error::new_spanned(lit_int, "status code must be between 100 and 599"));
+                    }
+                    status = Some(code);
+                }
+                syn::Meta::NameValue(nv) if nv.path.is_ident("priority") => {
+                    let (negative, lit_int) = match &nv.value {
+                        syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Int(lit_int), .. }) => (false, lit_int),
+                        syn::Expr::Unary(syn::ExprUnary { op: syn::UnOp::Neg(_), expr, .. }) => match &**expr {
+                            syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Int(lit_int), .. }) => (true, lit_int),
+                            _ => return Err(syn::Error::new_spanned(&nv.value, "expected integer priority")),
+                        },
+                        _ => return Err(syn::Error::new_spanned(&nv.value, "expected integer priority")),
+                    };
+                    let value: i32 = lit_int.base10_parse()?;
+                    priority = Some(if negative { -value } else { value });
+                }
+                syn::Meta::NameValue(nv) if nv.path.is_ident("guard") => {
+                    guards.push(nv.value.clone());
+                }
+                syn::Meta::NameValue(nv) => {
+                    let value = expect_str(&nv.value)?;
+
+                    if nv.path.is_ident("query") {
+                        query = Some(value);
+                    } else if nv.path.is_ident("path") && path.is_none() {
+                        path = Some(value);
+                    } else if nv.path.is_ident("method") {
+                        method = Some(value);
+                    } else if nv.path.is_ident("host") {
+                        host_param_names(&value.value()).map_err(|message| syn::Error::new_spanned(&value, message))?;
+                        host = Some(value);
+                    } else if nv.path.is_ident("name") {
+                        if value.value().trim().is_empty() {
+                            return Err(syn::Error::new_spanned(&value, "route name cannot be empty"));
+                        }
+                        name = Some(value);
+                    } else if nv.path.is_ident("content_type") {
+                        expect_media_type(&value)?;
+                        content_type = Some(value);
+                    } else if nv.path.is_ident("consumes") {
+                        expect_media_type(&value)?;
+                        consumes = Some(value);
+                    } else if nv.path.is_ident("produces") {
+                        expect_media_type(&value)?;
+                        produces = Some(value);
+                    } else {
+                        return Err(syn::Error::new_spanned(&nv.path, UNKNOWN_ROUTE_ARGUMENT));
+                    }
+                }
+                syn::Meta::List(list) if list.path.is_ident("headers") => {
+                    let entries = list.parse_args_with(
+                        syn::punctuated::Punctuated::<HeaderArg, syn::Token![,]>::parse_terminated,
+                    )?;
+                    headers.extend(entries.into_iter().map(|entry| (entry.name, entry.value)));
+                }
+                syn::Meta::List(list) if list.path.is_ident("header") => {
+                    let entries = list.parse_args_with(
+                        syn::punctuated::Punctuated::<HeaderArg, syn::Token![,]>::parse_terminated,
+                    )?;
+                    header_guards.extend(entries.into_iter().map(|entry| (entry.name, entry.value)));
+                }
+                other => return Err(syn::Error::new_spanned(other, UNKNOWN_ROUTE_ARGUMENT)),
+            }
+
+            if !input.is_empty() {
+                input.parse::<syn::Token![,]>()?;
+            }
+        }
+
+        let path = path.ok_or_else(|| input.error("expected route path"))?;
+
+        Ok(HandlerArgs {
+            method,
+            path,
+            query,
+            status,
+            headers,
+            content_type,
+            name,
+            host,
+            consumes,
+            produces,
+            header_guards,
+            guards,
+            priority,
+        })
+    }
+}
+
+/// Arguments of `#[handler("/prefix", host = "...", tags = [...], middleware = [...])]`.
+#[derive(Default)]
+pub(crate) struct ImplArgs {
+    pub(crate) prefix: Option<LitStr>,
+    pub(crate) host: Option<LitStr>,
+    pub(crate) tags: Vec<LitStr>,
+    pub(crate) middleware: Vec<syn::Path>,
+}
+
+impl Parse for ImplArgs {
+    fn parse(input: &ParseBuffer) -> syn::Result<Self> {
+        let mut args = ImplArgs::default();
+
+        if input.peek(LitStr) {
+            args.prefix = Some(input.parse()?);
+            if !input.is_empty() {
+                input.parse::<syn::Token![,]>()?;
+            }
+        }
+
+        while !input.is_empty() {
+            let nv: syn::MetaNameValue = input.parse()?;
+            if nv.path.is_ident("host") {
+                let host = expect_str(&nv.value)?;
+                host_param_names(&host.value()).map_err(|message| syn::Error::new_spanned(&host, message))?;
+                args.host = Some(host);
+                if !input.is_empty() {
+                    input.parse::<syn::Token![,]>()?;
+                }
+                continue;
+            }
+            let syn::Expr::Array(array) = &nv.value else {
+                return Err(syn::Error::new_spanned(&nv.value, "expected a `[...]` list"));
+            };
+
+            if nv.path.is_ident("tags") {
+                for elem in &array.elems {
+                    match elem {
+                        syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(tag), .. }) => args.tags.push(tag.clone()),
+                        other => return Err(syn::Error::new_spanned(other, "expected string literal tag")),
+                    }
+                }
+            } else if nv.path.is_ident("middleware") {
+                for elem in &array.elems {
+                    match elem {
+                        syn::Expr::Path(expr_path) => args.middleware.push(expr_path.path.clone()),
+                        other => return Err(syn::Error::new_spanned(other, "expected path to a middleware function")),
+                    }
+                }
+            } else {
+                return Err(syn::Error::new_spanned(&nv.path, "expected `host`, `tags` or `middleware` argument"));
+            }
+
+            if !input.is_empty() {
+                input.parse::<syn::Token![,]>()?;
+            }
+        }
+
+        Ok(args)
+    }
+}
+
+/// Extractors that consume the request body, which a path parameter argument can never be.
+pub(crate) const BODY_EXTRACTORS: [&str; 4] = ["Json", "Form", "Multipart", "Request"];
+
+/// Wrapper and extractor types that are not `FromStr`, so an argument named
+/// after a path parameter cannot be one of them.
+pub(crate) const NON_PARAM_TYPES: [&str; 7] = ["Option", "Result", "Vec", "Path", "Host", "Query", "State"];
+
+/// Returns the `{name}` parameters of a route path with their `{name:constraint}`,
+/// parsed with the router's own grammar.
+pub(crate) fn path_params(path: &str) -> Result<Vec<(String, Option<String>)>, String> {
+    let pattern = PathPattern::parse(path)?;
+    Ok(pattern.params()
+        .map(|param| (param.name.clone(), param.constraint.as_ref().map(ToString::to_string)))
+        .collect())
+}
+
+/// Argument types that agree with a `{name:constraint}`: the constrained type
+/// itself or a `String`. Regex constraints accept any type.
+pub(crate) fn constraint_arg_types(constraint: &str) -> Option<[&str; 2]> {
+    match constraint {
+        "uuid" => Some(["Uuid", "String"]),
+        _ => TYPE_CONSTRAINTS.iter().find(|name| **name == constraint).map(|name| [*name, "String"]),
+    }
+}
+
+/// Returns the `{name}` labels of a host pattern like `{tenant}.example.com`,
+/// rejecting malformed labels.
+pub(crate) fn host_param_names(host: &str) -> Result<Vec<String>, String> {
+    let mut names: Vec<String> = Vec::new();
+
+    for label in host.trim_end_matches('.').split('.') {
+        if let Some(name) = label.strip_prefix('{').and_then(|label| label.strip_suffix('}')) {
+            if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+                return Err(format!("invalid host parameter `{label}` in host `{host}`"));
+            }
+            if names.iter().any(|existing| existing == name) {
+                return Err(format!("host parameter `{{{name}}}` appears more than once in `{host}`"));
+            }
+            names.push(name.to_string());
+        } else if label.is_empty() || !label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+            return Err(format!("invalid label `{label}` in host `{host}`; use letters, digits, `-` or a whole-label `{{name}}`"));
+        }
+    }
+
+    Ok(names)
+}
+
+/// The last path segment of a type, e.g. `Json` for `maden_core::Json<T>`.
+pub(crate) fn type_name(ty: &Type) -> Option<String> {
+    match ty {
+        Type::Path(type_path) => type_path.path.segments.last().map(|segment| segment.ident.to_string()),
+        _ => None,
+    }
+}
+
+pub(crate) const ROUTE_ATTRIBUTES: [&str; 8] = ["get", "post", "put", "delete", "patch", "head", "options", "route"];
+
+/// Maps an HTTP method name to the matching `maden_core::HttpMethod` expression.
+pub(crate) fn http_method_tokens(name: &str) -> proc_macro2::TokenStream {
+    match name.to_ascii_uppercase().as_str() {
+        "GET" => quote! { maden_core::HttpMethod::Get },
+        "POST" => quote! { maden_core::HttpMethod::Post },
+        "PUT" => quote! { maden_core::HttpMethod::Put },
+        "DELETE" => quote! { maden_core::HttpMethod::Delete },
+        "PATCH" => quote! { maden_core::HttpMethod::Patch },
+        "OPTIONS" => quote! { maden_core::HttpMethod::Options },
+        "HEAD" => quote! { maden_core::HttpMethod::Head },
+        "TRACE" => quote! { maden_core::HttpMethod::Trace },
+        "CONNECT" => quote! { maden_core::HttpMethod::Connect },
+        other => quote! { maden_core::HttpMethod::Custom(#other.to_string()) },
+    }
+}
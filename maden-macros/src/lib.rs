@@ -1,287 +1,154 @@
+mod args;
+mod route;
+
 use heck::ToSnakeCase;
 use proc_macro::TokenStream;
 use quote::quote;
-use syn::{parse_macro_input, ImplItem, ItemFn, ItemImpl, LitStr, Type, Ident, FnArg, Pat, PatType};
-use syn::parse::{Parse, ParseBuffer};
+use syn::{parse_macro_input, ImplItem, ItemFn, ItemImpl, Type, Ident};
 
-struct HandlerArgs {
-    path: LitStr,
-    query: Option<LitStr>,
-}
-
-impl Parse for HandlerArgs {
-    fn parse(input: &ParseBuffer) -> syn::Result<Self> {
-        let path: LitStr = input.parse()?;
-        let mut query: Option<LitStr> = None;
-
-        if input.peek(syn::Token![,]) {
-            input.parse::<syn::Token![,]>()?;
-            let nv: syn::MetaNameValue = input.parse()?;
-            if nv.path.is_ident("query") {
-                if let syn::Expr::Lit(expr_lit) = nv.value {
-                    if let syn::Lit::Str(lit_str) = expr_lit.lit {
-                        query = Some(lit_str);
-                    } else {
-                        return Err(input.error("expected string literal for query value"));
-                    }
-                } else {
-                    return Err(input.error("expected literal for query value"));
-                }
-            } else {
-                return Err(input.error("expected `query` argument"));
-            }
-        }
-
-        Ok(HandlerArgs { path, query })
-    }
-}
+use crate::args::{HandlerArgs, ImplArgs, ROUTE_ATTRIBUTES};
+use crate::route::{route_registration, HandlerCall, RouteSpec};
 
 #[proc_macro_attribute]
-pub fn handler(_attr: TokenStream, item: TokenStream) -> TokenStream {
+pub fn handler(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let impl_args = parse_macro_input!(attr as ImplArgs);
     let mut input_impl = parse_macro_input!(item as ItemImpl);
 
-    let struct_name = if let Type::Path(type_path) = &*input_impl.self_ty {
-        type_path
-            .path
-            .segments
-            .last()
-            .expect("Expected a struct name")
-            .ident
-            .clone()
-    } else {
-        panic!("#[handler] can only be applied to impl blocks for structs");
+    let prefix = impl_args.prefix.as_ref().map(syn::LitStr::value).unwrap_or_default();
+    let route_tags = &impl_args.tags;
+    let route_middleware: Vec<_> = impl_args.middleware.iter()
+        .map(|path| {
+            let name = quote!(#path).to_string().replace(' ', "");
+            quote! { maden_core::Middleware::from_fn(#name, #path) }
+        })
+        .collect();
+
+    let struct_name = match &*input_impl.self_ty {
+        Type::Path(type_path) if type_path.qself.is_none() => match type_path.path.segments.last() {
+            Some(segment) => segment.ident.clone(),
+            None => {
+                return syn::Error::new_spanned(&input_impl.self_ty, "expected a struct name")
+                    .to_compile_error()
+                    .into();
+            }
+        },
+        other => {
+            return syn::Error::new_spanned(other, "#[handler] can only be applied to impl blocks for structs")
+                .to_compile_error()
+                .into();
+        }
     };
 
     let mut routes_registration = Vec::new();
+    let mut uses_instance = false;
+    let mut errors: Vec<syn::Error> = Vec::new();
 
     for impl_item in &mut input_impl.items {
         if let ImplItem::Fn(method) = impl_item {
             let method_name = &method.sig.ident;
-            let mut http_method = None;
-            let mut path_str = None;
-            let mut query_str = None;
+            let mut route_spec: Option<RouteSpec> = None;
 
             method.attrs.retain(|attr| {
-                let mut is_handled = false;
-                if attr.path().is_ident("get") || attr.path().is_ident("post") || attr.path().is_ident("put") || attr.path().is_ident("delete") {
-                    if let Ok(args) = attr.parse_args::<HandlerArgs>() {
-                        path_str = Some(args.path.value());
-                        query_str = args.query.map(|q| q.value());
-
-                        if attr.path().is_ident("get") {
-                            http_method = Some(quote! { maden_core::HttpMethod::Get });
-                        } else if attr.path().is_ident("post") {
-                            http_method = Some(quote! { maden_core::HttpMethod::Post });
-                        } else if attr.path().is_ident("put") {
-                            http_method = Some(quote! { maden_core::HttpMethod::Put });
-                        } else if attr.path().is_ident("delete") {
-                            http_method = Some(quote! { maden_core::HttpMethod::Delete });
-                        }
-                        is_handled = true;
-                    }
+                let Some(attr_name) = ROUTE_ATTRIBUTES.iter().find(|name| attr.path().is_ident(name)) else {
+                    return true;
+                };
+                if route_spec.is_some() {
+                    errors.push(syn::Error::new_spanned(attr, "a handler method can only have one route attribute"));
+                    return false;
                 }
-                !is_handled
+
+                let spec = attr.parse_args::<HandlerArgs>()
+                    .and_then(|args| {
+                        let default_name = format!("{struct_name}::{method_name}");
+                        RouteSpec::resolve(attr_name, args, &prefix, default_name, syn::spanned::Spanned::span(attr))
+                    });
+                match spec {
+                    Ok(spec) => route_spec = Some(spec),
+                    Err(e) => errors.push(e),
+                }
+                false
             });
 
-            if let (Some(http_method), Some(path)) = (http_method, path_str) {
-                let query_arg = if let Some(qs) = query_str {
-                    quote! { Some(#qs) }
-                } else {
-                    quote! { None }
-                };
+            let Some(mut spec) = route_spec else {
+                continue;
+            };
+            if let Some(host) = &impl_args.host {
+                spec.inherit_host(host);
+            }
+            for tag in route_tags {
+                spec.push_option(quote! { .tag(#tag) });
+            }
+            for middleware in &route_middleware {
+                spec.push_option(quote! { .middleware(#middleware) });
+            }
 
-                // Parse function parameters to generate extraction code
-                let mut param_extractions = Vec::new();
-                let mut param_names = Vec::new();
-                let mut path_params = Vec::new();
-
-                // Extract path parameter names from the route path
-                let path_param_names: Vec<String> = path.split('/')
-                    .filter_map(|segment| {
-                        if segment.starts_with('{') && segment.ends_with('}') {
-                            Some(segment[1..segment.len()-1].to_string())
-                        } else {
-                            None
-                        }
-                    })
-                    .collect();
-
-                for input in &method.sig.inputs {
-                    if let FnArg::Typed(PatType { pat, ty, .. }) = input {
-                        // Handle different pattern types
-                        match &**pat {
-                            Pat::Ident(pat_ident) => {
-                                let param_name = &pat_ident.ident;
-                                let param_name_str = param_name.to_string();
-                                
-                                // Check if it's the Request type - pass it directly
-                                if let Type::Path(type_path) = &**ty {
-                                    let type_name = &type_path.path.segments.last().unwrap().ident;
-                                    
-                                    if type_name == "Request" {
-                                        // Pass Request directly
-                                        param_extractions.push(quote! {
-                                            let #param_name = req.clone();
-                                        });
-                                        param_names.push(param_name.clone());
-                                        continue;
-                                    }
-                                }
-                                
-                                // Check if this parameter matches a path parameter
-                                if path_param_names.contains(&param_name_str) {
-                                    // This is a path parameter - extract it directly
-                                    path_params.push(param_name.clone());
-                                    param_extractions.push(quote! {
-                                        let #param_name = maden_core::extract_path_param::<#ty>(&req, #param_name_str)?;
-                                    });
-                                    param_names.push(param_name.clone());
-                                } else {
-                                    // Check if it's a wrapper type (Path, Query, Json)
-                                    if let Type::Path(type_path) = &**ty {
-                                        let type_name = &type_path.path.segments.last().unwrap().ident;
-                                        
-                                        if type_name == "Path" || type_name == "Query" || type_name == "Json" {
-                                            // Extract using FromRequest trait
-                                            param_extractions.push(quote! {
-                                                let #param_name = <#ty as maden_core::FromRequest>::from_request(&req).await?;
-                                            });
-                                            param_names.push(param_name.clone());
-                                        } else {
-                                            // Try to extract as JSON body for custom types
-                                            param_extractions.push(quote! {
-                                                let #param_name = {
-                                                    let body_str = String::from_utf8(req.body.clone())
-                                                        .map_err(|e| maden_core::MadenError::bad_request(format!("Invalid UTF-8 in request body: {}", e)))?;
-                                                    serde_json::from_str::<#ty>(&body_str)
-                                                        .map_err(|e| maden_core::MadenError::bad_request(format!("Failed to parse JSON body: {}", e)))?
-                                                };
-                                            });
-                                            param_names.push(param_name.clone());
-                                        }
-                                    } else {
-                                        // For other types, try to parse as JSON body
-                                        param_extractions.push(quote! {
-                                            let #param_name = {
-                                                let body_str = String::from_utf8(req.body.clone())
-                                                    .map_err(|e| maden_core::MadenError::bad_request(format!("Invalid UTF-8 in request body: {}", e)))?;
-                                                serde_json::from_str::<#ty>(&body_str)
-                                                    .map_err(|e| maden_core::MadenError::bad_request(format!("Failed to parse JSON body: {}", e)))?
-                                            };
-                                        });
-                                        param_names.push(param_name.clone());
-                                    }
-                                }
-                            },
-                            Pat::TupleStruct(tuple_struct) => {
-                                // Handle patterns like Query(query), Json(data), Path(params)
-                                if let Some(wrapper_name) = tuple_struct.path.segments.last() {
-                                    let wrapper_ident = &wrapper_name.ident;
-                                    
-                                    if wrapper_ident == "Query" || wrapper_ident == "Json" || wrapper_ident == "Path" {
-                                        // Extract the inner variable name
-                                        if let Some(Pat::Ident(inner_pat)) = tuple_struct.elems.first() {
-                                            let inner_name = &inner_pat.ident;
-                                            
-                                            // Extract using FromRequest trait
-                                            param_extractions.push(quote! {
-                                                let #wrapper_ident(#inner_name) = <#ty as maden_core::FromRequest>::from_request(&req).await?;
-                                            });
-                                            param_names.push(inner_name.clone());
-                                        }
-                                    }
-                                }
-                            },
-                            _ => {
-                                // Handle other pattern types if needed
-                            }
-                        }
+            // `&self` handlers are called on the shared instance, others as associated functions
+            let handler_call = match method.sig.receiver() {
+                Some(receiver) if receiver.reference.is_some() && receiver.mutability.is_none() => {
+                    uses_instance = true;
+                    HandlerCall {
+                        name: format!("{struct_name}::{method_name}"),
+                        call: quote! { this.#method_name },
+                        capture: quote! { let this = instance.clone(); },
+                        per_request: quote! { let this = this.clone(); },
                     }
                 }
+                Some(receiver) => {
+                    errors.push(syn::Error::new_spanned(receiver, "handler methods can only take `&self`"));
+                    continue;
+                }
+                None => HandlerCall {
+                    name: format!("{struct_name}::{method_name}"),
+                    call: quote! { #struct_name::#method_name },
+                    capture: quote! {},
+                    per_request: quote! {},
+                },
+            };
 
-                let return_type = &method.sig.output;
-                let response_conversion = match return_type {
-                    syn::ReturnType::Default => { // -> ()
-                        quote! { 
-                            #(#param_extractions)*
-                            #struct_name::#method_name(#(#param_names),*).await;
-                            Ok(maden_core::Response::new(200))
-                        }
-                    },
-                    syn::ReturnType::Type(_, ty) => { // -> Type
-                        if let Type::Path(type_path) = &**ty {
-                            let last_segment = type_path.path.segments.last().unwrap();
-                            let type_name = &last_segment.ident;
-
-                            if type_name == "Response" {
-                                quote! { 
-                                    #(#param_extractions)*
-                                    Ok(#struct_name::#method_name(#(#param_names),*).await)
-                                }
-                            } else if type_name == "String" {
-                                quote! { 
-                                    #(#param_extractions)*
-                                    let result = #struct_name::#method_name(#(#param_names),*).await;
-                                    Ok(maden_core::Response::new(200).text(&result))
-                                }
-                            } else if type_name == "Result" {
-                                quote! {
-                                    #(#param_extractions)*
-                                    match #struct_name::#method_name(#(#param_names),*).await {
-                                        Ok(value) => Ok(maden_core::Response::new(200).json(value)),
-                                        Err(err) => Ok(err.into_response()),
-                                    }
-                                }
-                            } else {
-                                // Assume it's a serializable type
-                                quote! { 
-                                    #(#param_extractions)*
-                                    let result = #struct_name::#method_name(#(#param_names),*).await;
-                                    Ok(maden_core::Response::new(200).json(result))
-                                }
-                            }
-                        } else {
-                            // Fallback for other complex types
-                            quote! { 
-                                #(#param_extractions)*
-                                let result = #struct_name::#method_name(#(#param_names),*).await;
-                                Ok(maden_core::Response::new(200).json(result))
-                            }
-                        }
-                    }
-                };
-
-                routes_registration.push(quote! {
-                    maden.add_route(
-                        #http_method,
-                        &#path,
-                        #query_arg,
-                        Box::new(|req| Box::pin(async move { 
-                            let result: Result<maden_core::Response, maden_core::MadenError> = async {
-                                #response_conversion
-                            }.await;
-                            
-                            match result {
-                                Ok(response) => response,
-                                Err(error) => error.into_response(),
-                            }
-                        })),
-                    );
-                });
+            if let Some(registration) = route_registration(&spec, &method.sig, handler_call, &mut errors) {
+                routes_registration.push(registration);
             }
         }
     }
 
+    if let Some(error) = combine_errors(errors) {
+        let compile_errors = error.to_compile_error();
+        return quote! {
+            #input_impl
+            #compile_errors
+        }.into();
+    }
+
     let add_routes_fn_name = Ident::new(
         &format!("add_routes_{}", struct_name.to_string().to_snake_case()),
         struct_name.span(),
     );
 
+    let self_ty = &input_impl.self_ty;
+    let instance = if uses_instance {
+        let missing_instance = format!(
+            "#[handler] `{struct_name}` has `&self` methods but no instance: implement `Default` or register one with `Maden::provide_handler`/`Maden::handler_constructor`"
+        );
+        quote! {
+            use maden_core::__private::{ViaDefault as _, ViaMissing as _};
+            let Some(instance): Option<std::sync::Arc<#self_ty>> = maden
+                .handler_instance::<#self_ty>()
+                .or_else(|| (&&maden_core::__private::InstanceProbe::<#self_ty>::new()).instance().map(std::sync::Arc::new))
+            else {
+                maden.setup_error(#missing_instance);
+                return;
+            };
+        }
+    } else {
+        quote! {}
+    };
+
     let expanded = quote! {
         #input_impl
 
         pub fn #add_routes_fn_name(maden: &mut maden_core::Maden) {
             use maden_core::IntoResponse;
+            #instance
             #(#routes_registration)*
         }
 
@@ -293,45 +160,156 @@ pub fn handler(_attr: TokenStream, item: TokenStream) -> TokenStream {
     expanded.into()
 }
 
+fn combine_errors(errors: Vec<syn::Error>) -> Option<syn::Error> {
+    errors.into_iter().reduce(|mut combined, error| {
+        combined.combine(error);
+        combined
+    })
+}
+
+/// Registers a standalone `async fn` as a route handler.
+///
+/// Inside a `#[handler]` impl the route attributes are consumed by `#[handler]`
+/// and never expand on their own.
+fn free_function_route(attr_name: &str, attr: TokenStream, item: TokenStream) -> TokenStream {
+    let input_fn = parse_macro_input!(item as ItemFn);
+    let fn_name = &input_fn.sig.ident;
+    let mut errors = Vec::new();
+
+    if let Some(receiver) = input_fn.sig.receiver() {
+        errors.push(syn::Error::new_spanned(receiver, "route methods taking `self` must be inside a #[handler] impl"));
+    }
+
+    let attr_span = syn::spanned::Spanned::span(&proc_macro2::TokenStream::from(attr.clone()));
+    let spec = syn::parse::<HandlerArgs>(attr)
+        .and_then(|args| RouteSpec::resolve(attr_name, args, "", fn_name.to_string(), attr_span));
+    let handler_call = HandlerCall {
+        name: fn_name.to_string(),
+        call: quote! { #fn_name },
+        capture: quote! {},
+        per_request: quote! {},
+    };
+    let registration = match spec {
+        Ok(spec) if errors.is_empty() => route_registration(&spec, &input_fn.sig, handler_call, &mut errors),
+        Ok(_) => None,
+        Err(e) => {
+            errors.push(e);
+            None
+        }
+    };
+
+    if let Some(error) = combine_errors(errors) {
+        let compile_errors = error.to_compile_error();
+        return quote! {
+            #input_fn
+            #compile_errors
+        }.into();
+    }
+
+    let add_route_fn_name = Ident::new(&format!("add_route_{fn_name}"), fn_name.span());
+
+    let expanded = quote! {
+        #input_fn
+
+        #[doc(hidden)]
+        pub fn #add_route_fn_name(maden: &mut maden_core::Maden) {
+            use maden_core::IntoResponse;
+            #registration
+        }
+
+        inventory::submit! {
+            maden_core::HandlerFactory(#add_route_fn_name)
+        }
+    };
+
+    expanded.into()
+}
+
 #[proc_macro_attribute]
-pub fn get(_attr: TokenStream, item: TokenStream) -> TokenStream {
-    item
+pub fn get(attr: TokenStream, item: TokenStream) -> TokenStream {
+    free_function_route("get", attr, item)
 }
 
 #[proc_macro_attribute]
-pub fn post(_attr: TokenStream, item: TokenStream) -> TokenStream {
-    item
+pub fn post(attr: TokenStream, item: TokenStream) -> TokenStream {
+    free_function_route("post", attr, item)
 }
 
 #[proc_macro_attribute]
-pub fn put(_attr: TokenStream, item: TokenStream) -> TokenStream {
-    item
+pub fn put(attr: TokenStream, item: TokenStream) -> TokenStream {
+    free_function_route("put", attr, item)
 }
 
 #[proc_macro_attribute]
-pub fn delete(_attr: TokenStream, item: TokenStream) -> TokenStream {
-    item
+pub fn delete(attr: TokenStream, item: TokenStream) -> TokenStream {
+    free_function_route("delete", attr, item)
+}
+
+#[proc_macro_attribute]
+pub fn patch(attr: TokenStream, item: TokenStream) -> TokenStream {
+    free_function_route("patch", attr, item)
+}
+
+#[proc_macro_attribute]
+pub fn head(attr: TokenStream, item: TokenStream) -> TokenStream {
+    free_function_route("head", attr, item)
+}
+
+#[proc_macro_attribute]
+pub fn options(attr: TokenStream, item: TokenStream) -> TokenStream {
+    free_function_route("options", attr, item)
+}
+
+#[proc_macro_attribute]
+pub fn route(attr: TokenStream, item: TokenStream) -> TokenStream {
+    free_function_route("route", attr, item)
 }
 
 #[proc_macro_attribute]
 pub fn application(_attr: TokenStream, item: TokenStream) -> TokenStream {
     let input_fn = parse_macro_input!(item as ItemFn);
     let fn_name = &input_fn.sig.ident;
+    let setup_inputs = &input_fn.sig.inputs;
+    let setup_body = &input_fn.block;
+
+    // `async fn main(maden: &mut Maden)` gets the app before it starts, e.g. to manage state.
+    let setup_call = if setup_inputs.is_empty() {
+        quote! { __maden_setup().await; }
+    } else {
+        quote! { __maden_setup(&mut maden).await; }
+    };
 
     let expanded = quote! {
         #[tokio::main]
         async fn #fn_name() {
+            async fn __maden_setup(#setup_inputs) #setup_body
+
             maden_log::init(); // Initialize the logger
-            let config = maden_config::load().expect("Failed to load server configuration");
             let mut maden = maden_core::Maden::new();
 
+            #setup_call
+
             for factory in inventory::iter::<maden_core::HandlerFactory>() {
                 (factory.0)(&mut maden);
             }
 
+            // `--routes` lists the route table instead of starting the server,
+            // failing like the server would on invalid routes
+            if std::env::args().skip(1).any(|arg| arg == "--routes") {
+                if let Err(errors) = maden.validate() {
+                    for error in &errors {
+                        eprintln!("error: {error}");
+                    }
+                    std::process::exit(1);
+                }
+                print!("{}", maden.route_table());
+                return;
+            }
+
+            let config = maden_config::load().expect("Failed to load server configuration");
             maden.run(config).await;
         }
     };
 
     expanded.into()
-}
\ No newline at end of file
+}
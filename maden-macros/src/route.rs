@@ -0,0 +1,288 @@
+use maden_pattern::join_path;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{quote, quote_spanned};
+use syn::{FnArg, Ident, LitStr, Pat, PatType, Signature};
+
+use crate::args::{
+    constraint_arg_types, host_param_names, http_method_tokens, is_token_char, path_params, type_name,
+    HandlerArgs, BODY_EXTRACTORS, NON_PARAM_TYPES,
+};
+
+/// A route attribute resolved to the method, full path and options it registers.
+pub(crate) struct RouteSpec {
+    http_method: TokenStream2,
+    path: String,
+    path_span: Span,
+    query: Option<String>,
+    host: Option<LitStr>,
+    options: Vec<TokenStream2>,
+}
+
+impl RouteSpec {
+    /// Resolves the arguments of `#[<attr_name>(...)]`, mounted under `prefix`.
+    /// The route is named `default_name` unless a `name = "..."` argument is given.
+    pub(crate) fn resolve(
+        attr_name: &str,
+        args: HandlerArgs,
+        prefix: &str,
+        default_name: String,
+        attr_span: Span,
+    ) -> syn::Result<Self> {
+        let method_name = match (attr_name, &args.method) {
+            ("route", Some(method)) => {
+                let name = method.value();
+                if name.is_empty() || !name.bytes().all(is_token_char) {
+                    return Err(syn::Error::new_spanned(method, "invalid HTTP method name"));
+                }
+                name
+            }
+            ("route", None) => {
+                return Err(syn::Error::new(attr_span, "#[route] requires a `method = \"...\"` argument"));
+            }
+            (name, Some(method)) => {
+                return Err(syn::Error::new_spanned(method, format!("`method` is only allowed in #[route], not #[{name}]")));
+            }
+            (name, None) => name.to_string(),
+        };
+
+        let name = args.name.map_or(default_name, |name| name.value());
+        let mut options = vec![quote! { .name(#name) }];
+
+        // Declarative success response options
+        if let Some(status) = args.status {
+            options.push(quote! { .status(#status) });
+        }
+        if let Some(content_type) = &args.content_type {
+            options.push(quote! { .content_type(#content_type) });
+        }
+        for (name, value) in &args.headers {
+            options.push(quote! { .response_header(#name, #value) });
+        }
+
+        // Guards deciding whether this route accepts a request
+        if let Some(consumes) = &args.consumes {
+            options.push(quote! { .consumes(#consumes) });
+        }
+        if let Some(produces) = &args.produces {
+            options.push(quote! { .produces(#produces) });
+        }
+        for (name, value) in &args.header_guards {
+            options.push(quote! { .require_header(#name, #value) });
+        }
+        for guard in &args.guards {
+            options.push(quote! { .guard(#guard) });
+        }
+        if let Some(priority) = args.priority {
+            options.push(quote! { .priority(#priority) });
+        }
+
+        Ok(Self {
+            http_method: http_method_tokens(&method_name),
+            path: join_path(prefix, &args.path.value()),
+            path_span: args.path.span(),
+            query: args.query.map(|q| q.value()),
+            host: args.host,
+            options,
+        })
+    }
+
+    /// Uses the impl-level host for routes that do not name their own.
+    pub(crate) fn inherit_host(&mut self, host: &LitStr) {
+        self.host.get_or_insert_with(|| host.clone());
+    }
+
+    /// Adds route-level options such as impl-level tags and middleware.
+    pub(crate) fn push_option(&mut self, option: TokenStream2) {
+        self.options.push(option);
+    }
+}
+
+/// How the generated route closure reaches the handler function.
+pub(crate) struct HandlerCall {
+    /// Handler path relative to its module, e.g. `Struct::method`.
+    pub(crate) name: String,
+    /// Path of the function, e.g. `this.method`, `Struct::method` or `function`.
+    pub(crate) call: TokenStream2,
+    /// Statement run once at registration, before the closure is built.
+    pub(crate) capture: TokenStream2,
+    /// Statement run by the closure for every request.
+    pub(crate) per_request: TokenStream2,
+}
+
+/// Generates the `maden.add_route(...)` statement for one handler function,
+/// pushing any problems with its signature onto `errors`.
+pub(crate) fn route_registration(
+    spec: &RouteSpec,
+    sig: &Signature,
+    handler: HandlerCall,
+    errors: &mut Vec<syn::Error>,
+) -> Option<TokenStream2> {
+    let fn_name = &sig.ident;
+
+    if sig.asyncness.is_none() {
+        errors.push(syn::Error::new_spanned(sig.fn_token, "route handlers must be `async fn`"));
+        return None;
+    }
+    if !sig.generics.params.is_empty() {
+        errors.push(syn::Error::new_spanned(&sig.generics, "route handlers cannot be generic"));
+        return None;
+    }
+
+    // Extract path parameter names from the route path
+    let path_params = match path_params(&spec.path) {
+        Ok(params) => params,
+        Err(message) => {
+            errors.push(syn::Error::new(spec.path_span, message));
+            return None;
+        }
+    };
+    let path_param_names: Vec<String> = path_params.iter().map(|(name, _)| name.clone()).collect();
+
+    // Host parameters are extracted like path parameters
+    let host_params = spec.host.as_ref()
+        .map(|host| host_param_names(&host.value()).unwrap_or_default())
+        .unwrap_or_default();
+    if let Some(name) = host_params.iter().find(|name| path_param_names.contains(name)) {
+        errors.push(syn::Error::new(spec.path_span, format!("`{{{name}}}` is captured by both the host and the path")));
+        return None;
+    }
+    let captured_names: Vec<&String> = path_param_names.iter().chain(&host_params).collect();
+
+    // Parse function parameters to generate extraction code
+    let mut param_extractions = Vec::new();
+    let mut param_names = Vec::new();
+
+    let typed_inputs: Vec<&PatType> = sig.inputs.iter()
+        .filter_map(|input| match input {
+            FnArg::Typed(pat_type) => Some(pat_type),
+            FnArg::Receiver(_) => None,
+        })
+        .collect();
+
+    for (index, PatType { pat, ty, .. }) in typed_inputs.iter().enumerate() {
+        let arg_name = Ident::new(&format!("__maden_arg_{index}"), Span::call_site());
+
+        let path_param_name = match &**pat {
+            Pat::Ident(pat_ident) => Some(pat_ident.ident.to_string())
+                .filter(|name| captured_names.contains(&name)),
+            _ => None,
+        };
+
+        if let (Some(param_name), Some(extractor)) = (&path_param_name, type_name(ty))
+            && BODY_EXTRACTORS.contains(&extractor.as_str())
+        {
+            errors.push(syn::Error::new_spanned(
+                ty,
+                format!("`{param_name}` matches the parameter `{{{param_name}}}` but is extracted as a `{extractor}` body; rename the argument or use the parameter's own type"),
+            ));
+        }
+
+        if let (Some(param_name), Some(wrapper)) = (&path_param_name, type_name(ty))
+            && NON_PARAM_TYPES.contains(&wrapper.as_str())
+        {
+            errors.push(syn::Error::new_spanned(
+                ty,
+                format!("path parameter `{param_name}` must be a `FromStr` type, not `{wrapper}`; use the parameter's own type, or give the argument another name"),
+            ));
+        }
+
+        if path_param_name.is_none()
+            && index + 1 != typed_inputs.len()
+            && let Some(extractor) = type_name(ty)
+            && BODY_EXTRACTORS.contains(&extractor.as_str())
+        {
+            errors.push(syn::Error::new_spanned(
+                ty,
+                format!("`{extractor}` consumes the request body, so it must be the last argument of `{fn_name}`"),
+            ));
+        }
+
+        // A typed `{name:type}` constraint must agree with the argument type
+        let constraint = path_params.iter()
+            .find(|(name, _)| Some(name) == path_param_name.as_ref())
+            .and_then(|(_, constraint)| constraint.as_deref());
+        if let Some(constraint) = constraint
+            && let Some(expected) = constraint_arg_types(constraint)
+            && !type_name(ty).is_some_and(|actual| expected.contains(&actual.as_str()))
+        {
+            let param_name = path_param_name.as_deref().unwrap_or_default();
+            errors.push(syn::Error::new_spanned(
+                ty,
+                format!("`{param_name}` is declared as `{{{param_name}:{constraint}}}` in the route path, so its type must be `{}` or `{}`", expected[0], expected[1]),
+            ));
+        }
+
+        if let Some(param_name_str) = path_param_name {
+            // Arguments named after a path parameter are parsed from that parameter
+            param_extractions.push(quote! {
+                let #arg_name = maden_core::extract_path_param::<#ty>(&req, #param_name_str)?;
+            });
+        } else if index + 1 == typed_inputs.len() {
+            // The last argument may consume the request body
+            param_extractions.push(quote! {
+                let #arg_name = <#ty as maden_core::FromRequest<_>>::from_request(req).await?;
+            });
+        } else {
+            param_extractions.push(quote! {
+                let #arg_name = <#ty as maden_core::FromRequestParts>::from_request_parts(&req).await?;
+            });
+        }
+        param_names.push(arg_name);
+    }
+
+    // Every path parameter needs an argument, unless one reads them all
+    let reads_all_params = typed_inputs.iter()
+        .any(|PatType { ty, .. }| type_name(ty).is_some_and(|name| name == "Path" || name == "Request"));
+    if !reads_all_params {
+        let arg_names: Vec<String> = typed_inputs.iter()
+            .filter_map(|PatType { pat, .. }| match &**pat {
+                Pat::Ident(pat_ident) => Some(pat_ident.ident.to_string()),
+                _ => None,
+            })
+            .collect();
+        for param in path_param_names.iter().filter(|param| !arg_names.contains(param)) {
+            errors.push(syn::Error::new(
+                spec.path_span,
+                format!("path parameter `{{{param}}}` has no matching argument in `{fn_name}`; add `{param}: T`, a `Path<T>` or a `Request` argument"),
+            ));
+        }
+    }
+
+    let RouteSpec { http_method, path, query, host, options, .. } = spec;
+    let host_option = host.as_ref().map(|host| quote! { .host(#host) });
+    let query_arg = match query {
+        Some(qs) => quote! { Some(#qs) },
+        None => quote! { None },
+    };
+    let HandlerCall { name, call, capture, per_request } = handler;
+
+    // Return values are converted through `IntoResponse`
+    let route_handler = quote! {
+        Box::new(move |req| {
+            #per_request
+            Box::pin(async move {
+                let result: Result<maden_core::Response, maden_core::MadenError> = async {
+                    #(#param_extractions)*
+                    Ok(#call(#(#param_names),*).await.into_response())
+                }.await;
+
+                match result {
+                    Ok(response) => response,
+                    Err(error) => error.into_response(),
+                }
+            })
+        })
+    };
+    // Spanned so the location recorded by `add_route` is the handler function
+    let add_route = quote_spanned! { fn_name.span()=>
+        maden.add_route(#http_method, #path, #query_arg, #route_handler)
+    };
+
+    Some(quote! {
+        #capture
+        #add_route
+            .handler_name(concat!(module_path!(), "::", #name))
+            #host_option
+            #(#options)*;
+    })
+}
@@ -0,0 +1,305 @@
+//! Route path grammar shared by `maden-core`, which matches requests against
+//! it, and `maden-macros`, which checks route paths at compile time.
+
+use std::fmt;
+
+use regex::Regex;
+
+/// Type names accepted as `{name:type}` constraints; anything else is a regex.
+pub const TYPE_CONSTRAINTS: [&str; 16] = [
+    "u8", "u16", "u32", "u64", "u128", "usize",
+    "i8", "i16", "i32", "i64", "i128", "isize",
+    "f32", "f64", "bool", "uuid",
+];
+
+/// What a path parameter must look like for its route to match,
+/// from `{id:u32}`, `{uuid:uuid}` or a regex as in `{slug:[a-z-]+}`.
+#[derive(Clone, Debug)]
+pub enum ParamConstraint {
+    Type(&'static str),
+    /// Anchored regex that must match the whole (decoded) value.
+    Pattern(Regex),
+}
+
+impl ParamConstraint {
+    pub fn parse(constraint: &str) -> Result<Self, regex::Error> {
+        match TYPE_CONSTRAINTS.iter().find(|name| **name == constraint) {
+            Some(name) => Ok(ParamConstraint::Type(name)),
+            None => Regex::new(&format!("^(?:{constraint})$")).map(ParamConstraint::Pattern),
+        }
+    }
+
+    pub fn matches(&self, value: &str) -> bool {
+        match self {
+            ParamConstraint::Type("u8") => value.parse::<u8>().is_ok(),
+            ParamConstraint::Type("u16") => value.parse::<u16>().is_ok(),
+            ParamConstraint::Type("u32") => value.parse::<u32>().is_ok(),
+            ParamConstraint::Type("u64") => value.parse::<u64>().is_ok(),
+            ParamConstraint::Type("u128") => value.parse::<u128>().is_ok(),
+            ParamConstraint::Type("usize") => value.parse::<usize>().is_ok(),
+            ParamConstraint::Type("i8") => value.parse::<i8>().is_ok(),
+            ParamConstraint::Type("i16") => value.parse::<i16>().is_ok(),
+            ParamConstraint::Type("i32") => value.parse::<i32>().is_ok(),
+            ParamConstraint::Type("i64") => value.parse::<i64>().is_ok(),
+            ParamConstraint::Type("i128") => value.parse::<i128>().is_ok(),
+            ParamConstraint::Type("isize") => value.parse::<isize>().is_ok(),
+            ParamConstraint::Type("f32") => value.parse::<f32>().is_ok(),
+            ParamConstraint::Type("f64") => value.parse::<f64>().is_ok(),
+            ParamConstraint::Type("bool") => value.parse::<bool>().is_ok(),
+            ParamConstraint::Type("uuid") => is_uuid(value),
+            ParamConstraint::Type(_) => false,
+            ParamConstraint::Pattern(regex) => regex.is_match(value),
+        }
+    }
+}
+
+impl PartialEq for ParamConstraint {
+    fn eq(&self, other: &Self) -> bool {
+        self.to_string() == other.to_string()
+    }
+}
+
+impl fmt::Display for ParamConstraint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParamConstraint::Type(name) => f.write_str(name),
+            ParamConstraint::Pattern(regex) => {
+                let anchored = regex.as_str();
+                f.write_str(anchored.strip_prefix("^(?:").and_then(|s| s.strip_suffix(")$")).unwrap_or(anchored))
+            }
+        }
+    }
+}
+
+/// Hyphenated UUID, e.g. `67e55044-10b1-426f-9247-bb680e5fe0c8`.
+fn is_uuid(value: &str) -> bool {
+    let groups: Vec<&str> = value.split('-').collect();
+    groups.len() == 5
+        && groups.iter().zip([8, 4, 4, 4, 12]).all(|(group, len)| {
+            group.len() == len && group.bytes().all(|b| b.is_ascii_hexdigit())
+        })
+}
+
+/// A `{name}`, `{*name}` or `{name:constraint}` parameter of a route path.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PathParam {
+    pub name: String,
+    pub catch_all: bool,
+    pub constraint: Option<ParamConstraint>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PathPart {
+    /// Literal text, with `{{`/`}}` escapes already resolved.
+    Literal(String),
+    Param(PathParam),
+}
+
+/// A route path split into literal text and parameters.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PathPattern {
+    pub parts: Vec<PathPart>,
+}
+
+impl PathPattern {
+    /// Splits `path` into parts, rejecting unmatched braces, empty or repeated
+    /// names, catch-alls before the end of the path and invalid constraints.
+    pub fn parse(path: &str) -> Result<Self, String> {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut chars = path.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '}' => {
+                    return Err(format!("unmatched `}}` in route path `{path}`; write `}}}}` for a literal brace"));
+                }
+                '{' => {
+                    // Constraints may contain braces themselves, e.g. `{code:[0-9]{3}}`
+                    let mut depth = 1;
+                    let mut inner = String::new();
+                    for c in chars.by_ref() {
+                        match c {
+                            '{' => depth += 1,
+                            '}' => depth -= 1,
+                            _ => {}
+                        }
+                        if depth == 0 {
+                            break;
+                        }
+                        inner.push(c);
+                    }
+                    if depth != 0 {
+                        return Err(format!("unclosed `{{` in route path `{path}`"));
+                    }
+
+                    let (name, constraint) = match inner.split_once(':') {
+                        Some((name, constraint)) => (name, Some(constraint)),
+                        None => (inner.as_str(), None),
+                    };
+                    let (catch_all, name) = match name.strip_prefix('*') {
+                        Some(name) => (true, name),
+                        None => (false, name),
+                    };
+                    if catch_all && chars.peek().is_some() {
+                        return Err(format!("catch-all parameter `{{{inner}}}` must be at the end of route path `{path}`"));
+                    }
+                    if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+                        return Err(format!("invalid path parameter `{{{inner}}}` in route path `{path}`"));
+                    }
+                    let repeated = parts.iter().any(|part| matches!(part, PathPart::Param(param) if param.name == name));
+                    if repeated {
+                        return Err(format!("path parameter `{{{name}}}` appears more than once in `{path}`"));
+                    }
+                    if constraint == Some("") {
+                        return Err(format!("empty constraint for `{{{name}}}` in route path `{path}`"));
+                    }
+                    let constraint = constraint
+                        .map(ParamConstraint::parse)
+                        .transpose()
+                        .map_err(|e| format!("invalid constraint for `{{{name}}}` in route path `{path}`: {e}"))?;
+
+                    if !literal.is_empty() {
+                        parts.push(PathPart::Literal(std::mem::take(&mut literal)));
+                    }
+                    parts.push(PathPart::Param(PathParam { name: name.to_string(), catch_all, constraint }));
+                }
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            parts.push(PathPart::Literal(literal));
+        }
+
+        Ok(Self { parts })
+    }
+
+    pub fn params(&self) -> impl Iterator<Item = &PathParam> {
+        self.parts.iter().filter_map(|part| match part {
+            PathPart::Param(param) => Some(param),
+            PathPart::Literal(_) => None,
+        })
+    }
+
+    /// The path with parameters renamed by position and constraints removed,
+    /// so routes that only differ in those (`/users/{id:u32}` and
+    /// `/users/{slug}`) share one matchit entry.
+    pub fn positional(&self) -> String {
+        let mut positional = String::new();
+        let mut index = 0;
+        for part in &self.parts {
+            match part {
+                PathPart::Literal(text) => positional.push_str(&text.replace('{', "{{").replace('}', "}}")),
+                PathPart::Param(param) => {
+                    let marker = if param.catch_all { "*" } else { "" };
+                    positional.push_str(&format!("{{{marker}p{index}}}"));
+                    index += 1;
+                }
+            }
+        }
+        positional
+    }
+}
+
+/// Joins a mount prefix and a route path, e.g. `/admin` and `/` into `/admin`.
+pub fn join_path(prefix: &str, path: &str) -> String {
+    let prefix = prefix.trim_end_matches('/');
+    if prefix.is_empty() {
+        path.to_string()
+    } else if path.is_empty() || path == "/" {
+        prefix.to_string()
+    } else if path.starts_with('/') {
+        format!("{prefix}{path}")
+    } else {
+        format!("{prefix}/{path}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn param(pattern: &PathPattern, index: usize) -> &PathParam {
+        pattern.params().nth(index).expect("parameter")
+    }
+
+    #[test]
+    fn parses_literals_params_and_constraints() {
+        let pattern = PathPattern::parse("/users/{id:u32}/files/{*rest}").unwrap();
+        assert_eq!(pattern.params().count(), 2);
+        assert_eq!(param(&pattern, 0).name, "id");
+        assert_eq!(param(&pattern, 0).constraint.as_ref().map(ToString::to_string).as_deref(), Some("u32"));
+        assert!(param(&pattern, 1).catch_all);
+        assert_eq!(pattern.positional(), "/users/{p0}/files/{*p1}");
+    }
+
+    #[test]
+    fn keeps_empty_segments_and_escaped_braces_literal() {
+        let pattern = PathPattern::parse("/a//{{b}}/{id}/").unwrap();
+        assert_eq!(pattern.parts[0], PathPart::Literal("/a//{b}/".to_string()));
+        assert_eq!(pattern.parts[2], PathPart::Literal("/".to_string()));
+        assert_eq!(pattern.positional(), "/a//{{b}}/{p0}/");
+    }
+
+    #[test]
+    fn regex_constraints_may_contain_braces() {
+        let pattern = PathPattern::parse("/codes/{code:[0-9]{3}}").unwrap();
+        let constraint = param(&pattern, 0).constraint.as_ref().unwrap();
+        assert_eq!(constraint.to_string(), "[0-9]{3}");
+        assert!(constraint.matches("404"));
+        assert!(!constraint.matches("4040"));
+    }
+
+    #[test]
+    fn rejects_malformed_paths() {
+        for (path, message) in [
+            ("/users/{id:}", "empty constraint"),
+            ("/users/{id", "unclosed `{`"),
+            ("/users/id}", "unmatched `}`"),
+            ("/users/{}", "invalid path parameter"),
+            ("/users/{a-b}", "invalid path parameter"),
+            ("/users/{id}/{id}", "appears more than once"),
+            ("/files/{*rest}/edit", "must be at the end"),
+            ("/users/{id:[0-9}", "invalid constraint"),
+        ] {
+            let error = PathPattern::parse(path).expect_err(path);
+            assert!(error.contains(message), "{path}: {error}");
+        }
+    }
+
+    #[test]
+    fn type_constraints_match_their_lexical_form() {
+        let constraint = |name| ParamConstraint::parse(name).unwrap();
+        assert!(constraint("u8").matches("255"));
+        assert!(!constraint("u8").matches("256"));
+        assert!(!constraint("u32").matches("abc"));
+        assert!(constraint("i32").matches("-7"));
+        assert!(constraint("bool").matches("true"));
+        assert!(!constraint("bool").matches("yes"));
+        assert!(constraint("uuid").matches("67e55044-10b1-426f-9247-bb680e5fe0c8"));
+        assert!(!constraint("uuid").matches("67e55044-10b1-426f-9247"));
+    }
+
+    #[test]
+    fn regex_constraints_are_anchored() {
+        let constraint = ParamConstraint::parse("[a-z]+").unwrap();
+        assert!(constraint.matches("slug"));
+        assert!(!constraint.matches("slug-1"));
+    }
+
+    #[test]
+    fn joins_prefix_and_path() {
+        assert_eq!(join_path("", "/users"), "/users");
+        assert_eq!(join_path("/admin/", "/"), "/admin");
+        assert_eq!(join_path("/admin", "stats"), "/admin/stats");
+        assert_eq!(join_path("/admin", "/stats"), "/admin/stats");
+    }
+}
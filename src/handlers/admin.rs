@@ -0,0 +1,35 @@
+use maden_core::{get, Json, Maden, MadenError, Middleware, Next, IntoResponse, Request, Response, State};
+
+use crate::handlers::test_handler::VisitCounter;
+
+// 관리자 앱에서만 쓰는 상태
+pub struct AdminConfig {
+    pub token: String,
+}
+
+// 관리자 토큰이 없으면 401 응답
+pub async fn require_admin_token(req: Request, next: Next) -> Response {
+    let expected = req.state.get::<AdminConfig>().map(|config| config.token.clone());
+    if expected.is_some() && req.headers.get("x-admin-token") == expected.as_ref() {
+        next.run(req).await
+    } else {
+        MadenError::unauthorized("Admin token required.").into_response()
+    }
+}
+
+// 자체 상태에 접근하면서 상위 앱의 상태도 함께 사용
+async fn stats(config: State<AdminConfig>, counter: State<VisitCounter>) -> Json<serde_json::Value> {
+    Json(serde_json::json!({
+        "visits": counter.current(),
+        "token_length": config.token.len(),
+    }))
+}
+
+// 독립된 Maden 앱으로 관리자 기능 구성 (main 에서 "/admin" 아래에 마운트)
+pub fn admin_app() -> Maden {
+    let mut admin = Maden::new()
+        .with_state(AdminConfig { token: "secret".to_string() })
+        .route("/stats", get(stats));
+    admin.middleware(Middleware::from_fn("require_admin_token", require_admin_token));
+    admin
+}
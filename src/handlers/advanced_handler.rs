@@ -1,5 +1,5 @@
 use maden_macros::handler;
-use maden_core::{Path, Query, Json, MadenError};
+use maden_core::{Form, Path, Query, Json, MadenError, RouteUrls, State, StatusCode};
 use serde::{Serialize, Deserialize};
 
 #[derive(Serialize, Deserialize)]
@@ -30,6 +30,22 @@ pub struct SearchQuery {
     pub limit: Option<u32>,
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct UserFilter {
+    pub zip: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub address: Option<AddressFilter>,
+    #[serde(default)]
+    pub verified: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct AddressFilter {
+    pub city: Option<String>,
+    pub country: Option<String>,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct UserParams {
     pub id: u32,
@@ -37,23 +53,35 @@ pub struct UserParams {
 
 pub struct AdvancedHandler;
 
-#[handler]
+#[handler("/users", tags = ["users"])]
 impl AdvancedHandler {
-    // 경로 매개변수를 직접 받기
-    #[get("/users/{id}")]
-    pub async fn get_user(id: u32) -> Result<User, MadenError> {
+    // 경로 매개변수를 직접 받기 (숫자가 아니면 이 라우트는 건너뜀)
+    #[get("/{id:u32}")]
+    pub async fn get_user(id: u32) -> Result<Json<User>, MadenError> {
         println!("Getting user with ID: {}", id);
-        Ok(User {
+        Ok(Json(User {
             id,
             name: format!("User {}", id),
             email: format!("user{}@example.com", id),
             active: true,
-        })
+        }))
+    }
+
+    // 정규식 제약으로 사용자 이름 받기 (타입 제약인 `{id:u32}` 라우트가 먼저 시도됨)
+    #[get("/{username:[a-z][a-z0-9_-]*}")]
+    pub async fn get_user_by_name(username: String) -> Result<Json<User>, MadenError> {
+        println!("Getting user with username: {}", username);
+        Ok(Json(User {
+            id: 0,
+            name: username.clone(),
+            email: format!("{}@example.com", username),
+            active: true,
+        }))
     }
 
     // 쿼리 매개변수를 구조체로 받기
-    #[get("/users")]
-    pub async fn search_users(Query(query): Query<SearchQuery>) -> Result<Vec<User>, MadenError> {
+    #[get("/")]
+    pub async fn search_users(Query(query): Query<SearchQuery>) -> Result<Json<Vec<User>>, MadenError> {
         println!("Searching users with query: {:?}", serde_json::to_string(&query).unwrap());
         
         let page = query.page.unwrap_or(1);
@@ -70,58 +98,87 @@ impl AdvancedHandler {
             });
         }
         
-        Ok(users)
+        Ok(Json(users))
+    }
+
+    // 반복 키(?tags=a&tags=b), 중첩 키(?address[city]=Seoul), 값 없는 플래그(?verified) 받기
+    #[get("/filter")]
+    pub async fn filter_users(Query(filter): Query<UserFilter>) -> Json<UserFilter> {
+        Json(filter)
     }
 
-    // JSON 바디를 구조체로 받기
-    #[post("/users")]
-    pub async fn create_user(Json(user_data): Json<CreateUserRequest>) -> Result<User, MadenError> {
+    // JSON 바디를 구조체로 받기 (Location 헤더는 라우트 이름으로 생성)
+    #[post("/")]
+    pub async fn create_user(
+        urls: State<RouteUrls>,
+        Json(user_data): Json<CreateUserRequest>,
+    ) -> Result<(StatusCode, [(&'static str, String); 1], Json<User>), MadenError> {
         println!("Creating user: {:?}", serde_json::to_string(&user_data).unwrap());
-        
-        Ok(User {
-            id: 999, // 실제로는 DB에서 생성된 ID
+
+        let id = 999; // 실제로는 DB에서 생성된 ID
+        let location = urls.url_for("AdvancedHandler::get_user", [("id", id)])?;
+
+        Ok((StatusCode::CREATED, [("Location", location)], Json(User {
+            id,
             name: user_data.name,
             email: user_data.email,
             active: true,
+        })))
+    }
+
+    // HTML 폼(application/x-www-form-urlencoded) 바디를 구조체로 받기
+    #[post("/form", status = 201)]
+    pub async fn create_user_from_form(Form(form): Form<CreateUserRequest>) -> Json<User> {
+        Json(User {
+            id: 1,
+            name: form.name,
+            email: form.email,
+            active: true,
         })
     }
 
     // 경로 매개변수와 JSON 바디를 함께 받기
-    #[put("/users/{id}")]
-    pub async fn update_user(id: u32, Json(update_data): Json<UpdateUserRequest>) -> Result<User, MadenError> {
+    #[put("/{id}")]
+    pub async fn update_user(id: u32, Json(update_data): Json<UpdateUserRequest>) -> Result<Json<User>, MadenError> {
         println!("Updating user {} with data: {:?}", id, serde_json::to_string(&update_data).unwrap());
         
-        Ok(User {
+        Ok(Json(User {
             id,
             name: update_data.name.unwrap_or_else(|| format!("User {}", id)),
             email: update_data.email.unwrap_or_else(|| format!("user{}@example.com", id)),
             active: update_data.active.unwrap_or(true),
-        })
+        }))
     }
 
     // 경로 매개변수를 구조체로 받기 (Path wrapper 사용)
-    #[delete("/users/{id}")]
+    #[delete("/{id}", content_type = "text/plain; charset=utf-8")]
     pub async fn delete_user(Path(params): Path<UserParams>) -> Result<String, MadenError> {
         println!("Deleting user with ID: {}", params.id);
         Ok(format!("User {} has been deleted", params.id))
     }
 
     // 복잡한 경로 매개변수 (여러 개)
-    #[get("/users/{user_id}/posts/{post_id}")]
-    pub async fn get_user_post(user_id: u32, post_id: u32) -> Result<serde_json::Value, MadenError> {
+    #[get("/{user_id}/posts/{post_id}")]
+    pub async fn get_user_post(user_id: u32, post_id: u32) -> Result<Json<serde_json::Value>, MadenError> {
         println!("Getting post {} for user {}", post_id, user_id);
         
-        Ok(serde_json::json!({
+        Ok(Json(serde_json::json!({
             "user_id": user_id,
             "post_id": post_id,
             "title": format!("Post {} by User {}", post_id, user_id),
             "content": "This is a sample post content."
-        }))
+        })))
+    }
+
+    // 튜플로 경로 매개변수를 순서대로 받기
+    #[get("/{id}/posts/{post_id}/comments")]
+    pub async fn get_post_comments(Path((id, post_id)): Path<(u32, u32)>) -> String {
+        format!("Comments on post {} by user {}", post_id, id)
     }
 
     // 쿼리 매개변수와 경로 매개변수를 함께 사용
-    #[get("/users/{id}/posts")]
-    pub async fn get_user_posts(id: u32, Query(query): Query<SearchQuery>) -> Result<Vec<serde_json::Value>, MadenError> {
+    #[get("/{id}/posts")]
+    pub async fn get_user_posts(id: u32, Query(query): Query<SearchQuery>) -> Result<Json<Vec<serde_json::Value>>, MadenError> {
         println!("Getting posts for user {} with query: {:?}", id, serde_json::to_string(&query).unwrap());
         
         let limit = query.limit.unwrap_or(5);
@@ -136,6 +193,6 @@ impl AdvancedHandler {
             }));
         }
         
-        Ok(posts)
+        Ok(Json(posts))
     }
 }
\ No newline at end of file
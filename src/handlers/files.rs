@@ -0,0 +1,17 @@
+use maden_macros::get;
+use maden_core::{Json, Request, StatusCode};
+
+// 나머지 경로 전체를 하나의 매개변수로 받기
+#[get("/files/{*path}")]
+pub async fn get_file(path: String) -> String {
+    println!("/files/{path}");
+    format!("GET /files received! Path: {path}")
+}
+
+// 어떤 라우트에도 맞지 않는 요청 처리 (Maden::fallback 으로 등록)
+pub async fn not_found(req: Request) -> (StatusCode, Json<serde_json::Value>) {
+    (StatusCode::NOT_FOUND, Json(serde_json::json!({
+        "status": 404,
+        "message": format!("No route for {}.", req.path),
+    })))
+}
@@ -0,0 +1,57 @@
+use maden_macros::{get, post};
+use maden_core::{Json, MadenError, Multipart, Request};
+
+// 헤더 값으로 라우트 선택하기 (조건이 맞지 않으면 다음 라우트로 넘어감)
+#[get("/versioned", header("X-Api-Version" = "2"))]
+pub async fn versioned_v2() -> &'static str {
+    "API v2"
+}
+
+// 조건이 같은 수준인 라우트끼리는 priority 가 높은 쪽을 먼저 시도
+#[get("/versioned", header("X-Beta" = "true"), priority = 1)]
+pub async fn versioned_beta() -> &'static str {
+    "API beta"
+}
+
+#[get("/versioned")]
+pub async fn versioned_v1() -> &'static str {
+    "API v1"
+}
+
+// Content-Type 이 다르면 415 응답
+// 멀티파트 필드를 스트리밍으로 읽고, 파일은 임시 디렉터리에 저장
+#[post("/upload", consumes = "multipart/form-data")]
+pub async fn upload(mut multipart: Multipart) -> Result<Json<serde_json::Value>, MadenError> {
+    let mut fields = Vec::new();
+    while let Some(field) = multipart.next_field().await? {
+        let name = field.name().unwrap_or_default().to_string();
+        if field.file_name().is_some() {
+            let file = field.spool().await?;
+            fields.push(serde_json::json!({
+                "name": name,
+                "file_name": file.file_name(),
+                "content_type": file.content_type(),
+                "size": file.size(),
+            }));
+        } else {
+            fields.push(serde_json::json!({ "name": name, "value": field.text().await? }));
+        }
+    }
+    Ok(Json(serde_json::json!({ "fields": fields })))
+}
+
+// Accept 헤더가 JSON 을 받지 않으면 406 응답
+#[get("/report", produces = "application/json")]
+pub async fn report() -> Json<serde_json::Value> {
+    Json(serde_json::json!({ "report": "ok" }))
+}
+
+// 직접 만든 조건 함수로 라우트 제한하기
+fn is_internal(req: &Request) -> bool {
+    req.headers.get("x-internal").is_some_and(|value| value == "true")
+}
+
+#[get("/internal", guard = is_internal)]
+pub async fn internal() -> &'static str {
+    "Internal endpoint"
+}
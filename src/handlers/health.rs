@@ -0,0 +1,21 @@
+use maden_macros::{get, head};
+use maden_core::{Json, State};
+
+use crate::handlers::test_handler::VisitCounter;
+
+// 구조체 없이 함수만으로 라우트 등록하기
+#[get("/health")]
+pub async fn health() -> &'static str {
+    "OK"
+}
+
+#[head("/health")]
+pub async fn health_head() {}
+
+#[get("/health/visits")]
+pub async fn health_visits(counter: State<VisitCounter>) -> Json<serde_json::Value> {
+    Json(serde_json::json!({
+        "status": "OK",
+        "visits": counter.current(),
+    }))
+}
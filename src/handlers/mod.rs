@@ -1,3 +1,10 @@
 pub mod test_handler;
 pub mod tmp_handler;
-pub mod simple_handler;
\ No newline at end of file
+pub mod simple_handler;
+pub mod advanced_handler;
+pub mod health;
+pub mod files;
+pub mod admin;
+pub mod tenant_handler;
+pub mod guarded;
+pub mod session;
\ No newline at end of file
@@ -0,0 +1,76 @@
+use maden_macros::{get, post};
+use maden_core::cookie::time::Duration;
+use maden_core::{Cookie, CookieJar, Json, PrivateCookieJar, Query, Response, SameSite, SignedCookieJar};
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+pub struct Prefs {
+    pub theme: String,
+    pub lang: Option<String>,
+}
+
+// 요청에 담긴 쿠키 모두 보기
+#[get("/cookies")]
+pub async fn list_cookies(jar: CookieJar) -> Json<serde_json::Value> {
+    let cookies: serde_json::Map<String, serde_json::Value> = jar.iter()
+        .map(|cookie| (cookie.name().to_string(), cookie.value().into()))
+        .collect();
+    Json(serde_json::Value::Object(cookies))
+}
+
+// 응답 헬퍼로 속성이 있는 쿠키 설정하기 (Set-Cookie 헤더 여러 개)
+#[post("/prefs")]
+pub async fn save_prefs(Query(prefs): Query<Prefs>) -> Response {
+    let mut response = Response::new(204).cookie(
+        Cookie::build(("theme", prefs.theme))
+            .path("/")
+            .max_age(Duration::days(365))
+            .same_site(SameSite::Lax),
+    );
+    if let Some(lang) = prefs.lang {
+        response = response.cookie(Cookie::build(("lang", lang)).path("/").max_age(Duration::days(365)));
+    }
+    response
+}
+
+// 서명된 쿠키: 클라이언트가 값을 바꾸면 무시됨 (maden.toml의 [cookie] secret 필요)
+#[get("/signed-visits")]
+pub async fn signed_visits(mut jar: SignedCookieJar) -> (SignedCookieJar, String) {
+    let count = jar.get("visits")
+        .and_then(|cookie| cookie.value().parse::<u32>().ok())
+        .unwrap_or(0) + 1;
+    jar.add(Cookie::build(("visits", count.to_string())).path("/"));
+    (jar, format!("Visit #{count}"))
+}
+
+// 암호화된 세션 쿠키로 로그인 / 로그아웃
+#[post("/login")]
+pub async fn login(mut jar: PrivateCookieJar, Query(user): Query<LoginQuery>) -> (PrivateCookieJar, String) {
+    let session = Cookie::build(("session", user.name.clone()))
+        .path("/")
+        .http_only(true)
+        .secure(true)
+        .same_site(SameSite::Strict)
+        .max_age(Duration::hours(1));
+    jar.add(session);
+    (jar, format!("Logged in as {}", user.name))
+}
+
+#[get("/me")]
+pub async fn me(jar: PrivateCookieJar) -> String {
+    match jar.get("session") {
+        Some(session) => format!("Hello, {}", session.value()),
+        None => "Not logged in".to_string(),
+    }
+}
+
+#[post("/logout")]
+pub async fn logout(mut jar: PrivateCookieJar) -> (PrivateCookieJar, &'static str) {
+    jar.remove(Cookie::build("session").path("/"));
+    (jar, "Logged out")
+}
+
+#[derive(Deserialize)]
+pub struct LoginQuery {
+    pub name: String,
+}
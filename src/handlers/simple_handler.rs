@@ -1,5 +1,5 @@
 use maden_macros::handler;
-use maden_core::{Request, Query, Json, MadenError};
+use maden_core::{Request, Response, Query, Json, MadenError, Next};
 use serde::{Serialize, Deserialize};
 
 #[derive(Serialize, Deserialize)]
@@ -15,47 +15,58 @@ pub struct CreateUserRequest {
     pub email: String,
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct PatchUserRequest {
+    pub name: Option<String>,
+    pub email: Option<String>,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct SearchParams {
     pub name: Option<String>,
     pub limit: Option<u32>,
 }
 
+// 모든 SimpleHandler 응답에 헤더 추가하기
+pub async fn tag_simple_response(req: Request, next: Next) -> Response {
+    next.run(req).await.with_header("X-Handled-By", "SimpleHandler")
+}
+
 pub struct SimpleHandler;
 
-#[handler]
+#[handler("/simple", tags = ["simple"], middleware = [tag_simple_response])]
 impl SimpleHandler {
     // 기본 Request 사용 (기존 방식)
-    #[get("/simple")]
+    #[get("/")]
     pub async fn simple_get(_req: Request) -> Result<String, MadenError> {
         Ok("Simple GET works!".to_string())
     }
 
     // 경로 매개변수를 직접 받기 (새로운 방식)
-    #[get("/simple/{id}")]
-    pub async fn get_by_id(id: u32) -> Result<SimpleUser, MadenError> {
+    #[get("/{id}")]
+    pub async fn get_by_id(id: u32) -> Result<Json<SimpleUser>, MadenError> {
         println!("Getting user with ID: {}", id);
-        Ok(SimpleUser {
+        Ok(Json(SimpleUser {
             id,
             name: format!("User {}", id),
             email: Some(format!("user{}@example.com", id)),
-        })
+        }))
     }
 
     // 여러 경로 매개변수
-    #[get("/simple/{user_id}/item/{item_id}")]
-    pub async fn get_user_item(user_id: u32, item_id: u32) -> Result<serde_json::Value, MadenError> {
+    #[get("/{user_id}/item/{item_id}")]
+    pub async fn get_user_item(user_id: u32, item_id: u32) -> Result<Json<serde_json::Value>, MadenError> {
         println!("Getting item {} for user {}", item_id, user_id);
-        Ok(serde_json::json!({
+        Ok(Json(serde_json::json!({
             "user_id": user_id,
             "item_id": item_id,
             "message": format!("Item {} belongs to User {}", item_id, user_id)
-        }))
+        })))
     }
 
     // 쿼리 매개변수를 구조체로 받기
-    #[get("/simple/search")]
-    pub async fn search_users(query: Query<SearchParams>) -> Result<Vec<SimpleUser>, MadenError> {
+    #[get("/search")]
+    pub async fn search_users(query: Query<SearchParams>) -> Result<Json<Vec<SimpleUser>>, MadenError> {
         let Query(params) = query;
         println!("Searching with params: {:?}", serde_json::to_string(&params).unwrap());
         
@@ -70,32 +81,64 @@ impl SimpleHandler {
             });
         }
         
-        Ok(users)
+        Ok(Json(users))
     }
 
     // JSON 바디를 구조체로 받기
-    #[post("/simple/users")]
-    pub async fn create_user(user_data: Json<CreateUserRequest>) -> Result<SimpleUser, MadenError> {
+    #[post("/users")]
+    pub async fn create_user(user_data: Json<CreateUserRequest>) -> Result<Json<SimpleUser>, MadenError> {
         let Json(data) = user_data;
         println!("Creating user: {:?}", serde_json::to_string(&data).unwrap());
         
-        Ok(SimpleUser {
+        Ok(Json(SimpleUser {
             id: 999, // 실제로는 DB에서 생성된 ID
             name: data.name,
             email: Some(data.email),
-        })
+        }))
     }
 
     // 경로 매개변수와 JSON 바디를 함께 받기
-    #[put("/simple/{id}")]
-    pub async fn update_user(id: u32, user_data: Json<CreateUserRequest>) -> Result<SimpleUser, MadenError> {
+    #[put("/{id}")]
+    pub async fn update_user(id: u32, user_data: Json<CreateUserRequest>) -> Result<Json<SimpleUser>, MadenError> {
         let Json(data) = user_data;
         println!("Updating user {} with data: {:?}", id, serde_json::to_string(&data).unwrap());
         
-        Ok(SimpleUser {
+        Ok(Json(SimpleUser {
             id,
             name: data.name,
             email: Some(data.email),
-        })
+        }))
+    }
+
+    // 일부 필드만 수정하기 (PATCH)
+    #[patch("/{id}")]
+    pub async fn patch_user(id: u32, user_data: Json<PatchUserRequest>) -> Result<Json<SimpleUser>, MadenError> {
+        let Json(data) = user_data;
+        println!("Patching user {} with data: {:?}", id, serde_json::to_string(&data).unwrap());
+
+        Ok(Json(SimpleUser {
+            id,
+            name: data.name.unwrap_or_else(|| format!("User {}", id)),
+            email: data.email,
+        }))
+    }
+
+    // OPTIONS 요청 직접 처리하기: CORS preflight 응답
+    // (Allow 헤더는 라우트 테이블에서 자동으로 붙으므로 여기서는 CORS 헤더만 설정)
+    #[options("/")]
+    pub async fn simple_options(req: Request) -> Response {
+        let mut response = Response::new(204)
+            .with_header("Access-Control-Allow-Origin", "*")
+            .with_header("Access-Control-Max-Age", "600");
+        // 이 경로에 등록된 메서드를 요청한 경우에만 허용
+        if let Some(method) = req.headers.get("access-control-request-method")
+            && req.allowed_methods().any(|allowed| allowed == method)
+        {
+            response = response.with_header("Access-Control-Allow-Methods", method);
+        }
+        if let Some(headers) = req.headers.get("access-control-request-headers") {
+            response = response.with_header("Access-Control-Allow-Headers", headers);
+        }
+        response
     }
 }
\ No newline at end of file
@@ -0,0 +1,32 @@
+use maden_macros::handler;
+use maden_core::{Host, Json, Path};
+
+pub struct TenantHandler;
+
+// 모든 라우트를 `{tenant}.localhost` 호스트로 제한
+#[handler(host = "{tenant}.localhost")]
+impl TenantHandler {
+    // 서브도메인에서 테넌트 이름 받기
+    #[get("/tenant")]
+    pub async fn tenant_info(tenant: String) -> Json<serde_json::Value> {
+        Json(serde_json::json!({ "tenant": tenant }))
+    }
+
+    // 정확한 호스트가 와일드카드 호스트보다 먼저 선택됨
+    #[get("/tenant", host = "admin.localhost")]
+    pub async fn admin_info() -> &'static str {
+        "Admin console"
+    }
+
+    // 호스트 매개변수와 경로 매개변수를 함께 받기
+    #[get("/tenant/projects/{project}")]
+    pub async fn tenant_project(tenant: String, project: String) -> String {
+        format!("Project {project} of tenant {tenant}")
+    }
+
+    // 경로 매개변수는 Path, 호스트 매개변수는 Host로 따로 받기
+    #[get("/tenant/files/{folder}/{file}")]
+    pub async fn tenant_file(Path((folder, file)): Path<(String, String)>, Host(tenant): Host<String>) -> String {
+        format!("File {folder}/{file} of tenant {tenant}")
+    }
+}
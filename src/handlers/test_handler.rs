@@ -1,5 +1,7 @@
 use maden_macros::{handler};
-use maden_core::{Request, MadenError};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use maden_core::{Request, MadenError, State, FromRequestParts, Json, Html};
 use serde::{Serialize, Deserialize};
 
 #[derive(Serialize, Deserialize)]
@@ -9,6 +11,33 @@ pub struct MyData {
     pub active: bool,
 }
 
+#[derive(Default)]
+pub struct VisitCounter {
+    count: AtomicU64,
+}
+
+impl VisitCounter {
+    pub fn increment(&self) -> u64 {
+        self.count.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    pub fn current(&self) -> u64 {
+        self.count.load(Ordering::Relaxed)
+    }
+}
+
+// 사용자 정의 추출기 예시
+pub struct UserAgent(pub String);
+
+impl FromRequestParts for UserAgent {
+    async fn from_request_parts(req: &Request) -> Result<Self, MadenError> {
+        req.headers.get("user-agent")
+            .cloned()
+            .map(UserAgent)
+            .ok_or_else(|| MadenError::bad_request("Missing User-Agent header."))
+    }
+}
+
 pub struct TestHandler;
 
 #[handler]
@@ -40,9 +69,9 @@ impl TestHandler {
     }
 
     #[get("/json-example")]
-    pub async fn json_example(_req: Request) -> Result<serde_json::Value, MadenError> {
+    pub async fn json_example(_req: Request) -> Result<Json<serde_json::Value>, MadenError> {
         println!("/json-example");
-        Ok(serde_json::json!({
+        Ok(Json(serde_json::json!({
             "status": "success",
             "message": "This is a JSON response from Maden.",
             "data": {
@@ -52,21 +81,27 @@ impl TestHandler {
                     "item": "example"
                 }
             }
-        }))
+        })))
+    }
+
+    #[get("/html-example")]
+    pub async fn html_example() -> Html<&'static str> {
+        println!("/html-example");
+        Html("<h1>Hello from Maden!</h1>")
     }
 
     #[get("/auto-json")]
-    pub async fn auto_json_response(_req: Request) -> Result<MyData, MadenError> {
+    pub async fn auto_json_response(_req: Request) -> Result<Json<MyData>, MadenError> {
         println!("/auto-json");
-        Ok(MyData {
+        Ok(Json(MyData {
             id: 1,
             name: "Auto JSON Test".to_string(),
             active: true,
-        })
+        }))
     }
 
     #[get("/error-example")]
-    pub async fn error_example(_req: Request) -> Result<MyData, MadenError> {
+    pub async fn error_example(_req: Request) -> Result<Json<MyData>, MadenError> {
         println!("/error-example");
         // 400 Bad Request 에러 반환 예시
         if _req.query_params.get("type").is_some_and(|s| s == "bad") {
@@ -81,10 +116,26 @@ impl TestHandler {
             return Err(MadenError::internal_server_error("Something went wrong on the server."));
         }
 
-        Ok(MyData {
+        Ok(Json(MyData {
             id: 2,
             name: "Error Example Success".to_string(),
             active: false,
-        })
+        }))
+    }
+
+    #[get("/visits")]
+    pub async fn visits(counter: State<VisitCounter>) -> Result<String, MadenError> {
+        let visits = counter.increment();
+        println!("/visits");
+        Ok(format!("GET /visits received! Visit count: {visits}"))
+    }
+
+    #[get("/whoami")]
+    pub async fn whoami(agent: Option<UserAgent>) -> Result<String, MadenError> {
+        println!("/whoami");
+        match agent {
+            Some(UserAgent(agent)) => Ok(format!("GET /whoami received! User-Agent: {agent}")),
+            None => Ok("GET /whoami received! No User-Agent header.".to_string()),
+        }
     }
 }
\ No newline at end of file
@@ -1,13 +1,46 @@
 use maden_macros::{handler};
-use maden_core::{Request, MadenError};
+use maden_core::{Request, MadenError, StatusCode};
 
-pub struct TmpHandler;
+pub struct TmpHandler {
+    greeting: String,
+}
+
+impl TmpHandler {
+    pub fn new(greeting: impl Into<String>) -> Self {
+        Self { greeting: greeting.into() }
+    }
+}
 
 #[handler]
 impl TmpHandler {
     #[get("/tmp")]
-    pub async fn get_tmp(_req: Request) -> Result<String, MadenError> {
+    pub async fn get_tmp(&self, _req: Request) -> Result<String, MadenError> {
         println!("/tmp");
-        Ok("Hello from TmpHandler!".to_string())
+        Ok(self.greeting.clone())
+    }
+
+    #[route(method = "PURGE", path = "/tmp")]
+    pub async fn purge_tmp(_req: Request) -> (StatusCode, [(&'static str, &'static str); 1], &'static str) {
+        println!("PURGE /tmp");
+        (StatusCode::ACCEPTED, [("X-Purged", "tmp")], "TmpHandler cache purged!")
+    }
+
+    #[get("/tmp", query = "action=export")]
+    pub async fn export_tmp(_req: Request) -> Result<String, MadenError> {
+        println!("/tmp?action=export");
+        Ok("Exporting from TmpHandler!".to_string())
+    }
+
+    #[get("/tmp", query = "action=import")]
+    pub async fn import_tmp(_req: Request) -> Result<String, MadenError> {
+        println!("/tmp?action=import");
+        Ok("Importing into TmpHandler!".to_string())
+    }
+
+    // 값 없는 키도 조건으로 사용 가능 (?verbose)
+    #[get("/tmp", query = "verbose")]
+    pub async fn verbose_tmp(&self, _req: Request) -> Result<String, MadenError> {
+        println!("/tmp?verbose");
+        Ok(format!("{} (verbose)", self.greeting))
     }
 }
\ No newline at end of file
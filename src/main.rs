@@ -1,8 +1,30 @@
+use maden_core::{get, Json, Maden, MultipartConfig};
 use maden_macros::application;
 
+use crate::handlers::admin::admin_app;
+use crate::handlers::files::not_found;
+use crate::handlers::test_handler::VisitCounter;
+use crate::handlers::tmp_handler::TmpHandler;
+
 mod handlers;
 
 #[application]
-async fn main() {
+async fn main(maden: &mut Maden) {
     // Logging initialization is now handled by maden-log crate via the #[application] macro
+    maden.manage(VisitCounter::default());
+    maden.handler_constructor(|_| TmpHandler::new("Hello from TmpHandler!"));
+    maden.fallback(not_found);
+
+    // 멀티파트 업로드 크기 제한 (필드별 / 전체)
+    maden.manage(MultipartConfig::new().max_field_size(8 * 1024 * 1024).field_limit("note", 1024));
+
+    // 매크로 없이 빌더로 라우트 등록하기
+    maden.add_routes(
+        "/ping",
+        get(|| async { "pong" })
+            .post(|Json(body): Json<serde_json::Value>| async move { Json(body) }),
+    );
+
+    // 독립된 앱을 접두사 아래에 마운트하기
+    maden.nest("/admin", admin_app());
 }